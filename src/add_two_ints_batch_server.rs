@@ -0,0 +1,78 @@
+use std::sync::Mutex;
+
+// rosmsg_include works properly only with linux line end i.e. LF
+// it does now work properly with windows CR LF!!
+mod srv {
+    rosrust::rosmsg_include!(
+        rosservices / AddTwoIntsBatch,
+        rosservices / AddTwoIntsStreamRequest,
+        rosservices / AddTwoIntsStreamResult
+    );
+}
+
+use srv::rosservices::{
+    AddTwoIntsBatch, AddTwoIntsBatchRes, AddTwoIntsStreamRequest, AddTwoIntsStreamResult,
+};
+
+fn main() {
+    rosrust::init("add_two_ints_batch_server");
+
+    // Batch: all additions in one round-trip
+    // The service is stopped when the returned object is destroyed
+    let _service_raii = rosrust::service::<AddTwoIntsBatch, _>("add_two_ints_batch", move |req| {
+        // Err is sent back to the client as service error
+        if req.a.len() != req.b.len() {
+            return Err(format!(
+                "got {} a operands but {} b operands",
+                req.a.len(),
+                req.b.len()
+            ));
+        }
+
+        let sums = req
+            .a
+            .iter()
+            .zip(&req.b)
+            .enumerate()
+            .map(|(i, (a, b))| {
+                a.checked_add(*b)
+                    .ok_or_else(|| format!("[{}]: {} + {} overflows int64", i, a, b))
+            })
+            .collect::<Result<Vec<i64>, String>>()?;
+
+        rosrust::ros_info!("added {} pairs", sums.len());
+
+        Ok(AddTwoIntsBatchRes { sums })
+    })
+    .unwrap();
+
+    // Streaming: each request message is answered by result message with the same id.
+    // No round-trip per request, client can send many requests without waiting.
+    let result_publisher = Mutex::new(
+        rosrust::publish::<AddTwoIntsStreamResult>("add_two_ints_stream/result", 1000).unwrap(),
+    );
+    let _subscriber = rosrust::subscribe(
+        "add_two_ints_stream/request",
+        1000,
+        move |req: AddTwoIntsStreamRequest| {
+            let result = match req.a.checked_add(req.b) {
+                Some(sum) => AddTwoIntsStreamResult {
+                    id: req.id,
+                    sum,
+                    error: String::new(),
+                },
+                None => AddTwoIntsStreamResult {
+                    id: req.id,
+                    sum: 0,
+                    error: format!("{} + {} overflows int64", req.a, req.b),
+                },
+            };
+            result_publisher.lock().unwrap().send(result).unwrap();
+        },
+    )
+    .unwrap();
+
+    rosrust::ros_info!("add_two_ints_batch service and add_two_ints_stream topics running!");
+
+    rosrust::spin();
+}
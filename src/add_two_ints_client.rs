@@ -1,4 +1,4 @@
-use std::time;
+use ros_tutorial::service_client::{CallPolicy, ServiceClient};
 
 mod srv {
     rosrust::rosmsg_include!(rosservices / AddTwoInts);
@@ -19,32 +19,42 @@ fn main() {
     // Initialize node
     rosrust::init("add_two_ints_client");
 
-    // Wait ten seconds for the service to appear
-    rosrust::ros_info!("checking for availability of add_two_ints service...");
-    rosrust::wait_for_service("add_two_ints", Some(time::Duration::from_secs(10))).unwrap();
-    rosrust::ros_info!("add_two_ints service found! calling...");
-
-    // Create client for the service
-    let client = rosrust::client::<srv::rosservices::AddTwoInts>("add_two_ints").unwrap();
-
-    // Synchronous call that blocks the thread until a response is received
-    rosrust::ros_info!(
-        "{} + {} = {}",
-        a,
-        b,
-        client
-            .req(&srv::rosservices::AddTwoIntsReq { a, b })
-            .unwrap()
-            .unwrap()
-            .sum
-    );
+    // Create client for the service, it waits for the service to appear,
+    // retries failed calls and reconnects when the server restarts
+    let mut client =
+        ServiceClient::<srv::rosservices::AddTwoInts>::new("add_two_ints", CallPolicy::default());
+
+    // Synchronous call that blocks the thread until a response is received (or timeout)
+    match client.call(&srv::rosservices::AddTwoIntsReq { a, b }) {
+        Ok(res) => rosrust::ros_info!("{} + {} = {}", a, b, res.sum),
+        Err(e) => {
+            rosrust::ros_err!("add_two_ints: {}", e);
+            return;
+        }
+    }
 
     // Asynchronous call that can be resolved later on/ Multiply a and b by two just to distinguish from previous call
-    let retval = client.req_async(srv::rosservices::AddTwoIntsReq { a: a * 2, b: b * 2 });
-    rosrust::ros_info!(
-        "{} + {} = {}",
-        a * 2,
-        b * 2,
-        retval.read().unwrap().unwrap().sum
-    );
+    let (a, b) = match (a.checked_mul(2), b.checked_mul(2)) {
+        (Some(a), Some(b)) => (a, b),
+        _ => {
+            rosrust::ros_err!(
+                "{} * 2 or {} * 2 overflows int64, skipping async call",
+                a,
+                b
+            );
+            return;
+        }
+    };
+    let retval = match client.client() {
+        Ok(client) => client.req_async(srv::rosservices::AddTwoIntsReq { a, b }),
+        Err(e) => {
+            rosrust::ros_err!("add_two_ints: {}", e);
+            return;
+        }
+    };
+    match retval.read() {
+        Ok(Ok(res)) => rosrust::ros_info!("{} + {} = {}", a, b, res.sum),
+        Ok(Err(e)) => rosrust::ros_err!("add_two_ints service error: {}", e),
+        Err(e) => rosrust::ros_err!("add_two_ints call failed: {}", e),
+    }
 }
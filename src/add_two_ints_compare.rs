@@ -0,0 +1,168 @@
+use std::collections::HashSet;
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::{Duration, Instant};
+
+// rosmsg_include works properly only with linux line end i.e. LF
+// it does now work properly with windows CR LF!!
+mod srv {
+    rosrust::rosmsg_include!(
+        rosservices / AddTwoInts,
+        rosservices / AddTwoIntsBatch,
+        rosservices / AddTwoIntsStreamRequest,
+        rosservices / AddTwoIntsStreamResult
+    );
+}
+
+use srv::rosservices::{
+    AddTwoInts, AddTwoIntsBatch, AddTwoIntsBatchReq, AddTwoIntsReq, AddTwoIntsStreamRequest,
+    AddTwoIntsStreamResult,
+};
+
+/// how long to wait for services and topic connections
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// how long to wait for all streamed results
+const STREAM_TIMEOUT: Duration = Duration::from_secs(30);
+
+fn report(name: &str, count: usize, elapsed: Duration) {
+    rosrust::ros_info!(
+        "{:<10} {:>6} additions in {:>9.3} ms, {:>8.3} ms per addition, {:>10.0} additions/s",
+        name,
+        count,
+        elapsed.as_secs_f64() * 1000.0,
+        elapsed.as_secs_f64() * 1000.0 / count as f64,
+        count as f64 / elapsed.as_secs_f64()
+    );
+}
+
+/// one service call per addition (add_two_ints_server)
+fn per_call(count: usize) {
+    if rosrust::wait_for_service("add_two_ints", Some(CONNECT_TIMEOUT)).is_err() {
+        rosrust::ros_warn!(
+            "add_two_ints service not available (run add-two-ints-server), skipping"
+        );
+        return;
+    }
+    let client = rosrust::client::<AddTwoInts>("add_two_ints").unwrap();
+
+    let t0 = Instant::now();
+    for i in 0..count as i64 {
+        match client.req(&AddTwoIntsReq { a: i, b: i }) {
+            Ok(Ok(_)) => (),
+            Ok(Err(e)) => rosrust::ros_err!("add_two_ints service error: {}", e),
+            Err(e) => {
+                rosrust::ros_err!("add_two_ints call failed: {}", e);
+                return;
+            }
+        }
+    }
+    report("per call", count, t0.elapsed());
+}
+
+/// all additions in one service call (add_two_ints_batch_server)
+fn batch(count: usize) {
+    if rosrust::wait_for_service("add_two_ints_batch", Some(CONNECT_TIMEOUT)).is_err() {
+        rosrust::ros_warn!(
+            "add_two_ints_batch service not available (run add-two-ints-batch-server), skipping"
+        );
+        return;
+    }
+    let client = rosrust::client::<AddTwoIntsBatch>("add_two_ints_batch").unwrap();
+    let operands: Vec<i64> = (0..count as i64).collect();
+
+    let t0 = Instant::now();
+    match client.req(&AddTwoIntsBatchReq {
+        a: operands.clone(),
+        b: operands,
+    }) {
+        Ok(Ok(res)) if res.sums.len() == count => report("batch", count, t0.elapsed()),
+        Ok(Ok(res)) => rosrust::ros_err!("expected {} sums, got {}", count, res.sums.len()),
+        Ok(Err(e)) => rosrust::ros_err!("add_two_ints_batch service error: {}", e),
+        Err(e) => rosrust::ros_err!("add_two_ints_batch call failed: {}", e),
+    }
+}
+
+/// requests and results streamed over topics (add_two_ints_batch_server)
+fn streaming(count: usize) {
+    let received = Arc::new(Mutex::new(HashSet::new()));
+    let results = Arc::clone(&received);
+    let subscriber = rosrust::subscribe(
+        "add_two_ints_stream/result",
+        count,
+        move |result: AddTwoIntsStreamResult| {
+            if !result.error.is_empty() {
+                rosrust::ros_err!("[{}]: {}", result.id, result.error);
+            }
+            results.lock().unwrap().insert(result.id);
+        },
+    )
+    .unwrap();
+    let publisher =
+        rosrust::publish::<AddTwoIntsStreamRequest>("add_two_ints_stream/request", count).unwrap();
+
+    // messages sent before connections are established would be lost
+    let t0 = Instant::now();
+    while (publisher.subscriber_count() == 0 || subscriber.publisher_count() == 0)
+        && t0.elapsed() < CONNECT_TIMEOUT
+    {
+        thread::sleep(Duration::from_millis(50));
+    }
+    if publisher.subscriber_count() == 0 || subscriber.publisher_count() == 0 {
+        rosrust::ros_warn!(
+            "add_two_ints_stream topics not connected (run add-two-ints-batch-server), skipping"
+        );
+        return;
+    }
+
+    let t0 = Instant::now();
+    for i in 0..count {
+        let a = i as i64;
+        publisher
+            .send(AddTwoIntsStreamRequest {
+                id: i as u64,
+                a,
+                b: a,
+            })
+            .unwrap();
+    }
+
+    while received.lock().unwrap().len() < count && t0.elapsed() < STREAM_TIMEOUT {
+        thread::sleep(Duration::from_micros(100));
+    }
+
+    let elapsed = t0.elapsed();
+    let received = received.lock().unwrap().len();
+    if received < count {
+        rosrust::ros_warn!(
+            "streaming: only {} of {} results received (queue overflow?)",
+            received,
+            count
+        );
+    }
+    // per addition cost can not be computed without any result
+    if received > 0 {
+        report("streaming", received, elapsed);
+    }
+}
+
+/// compares cost of service call per addition, batch service call and streaming over topics
+fn main() {
+    // Fetch args that are not meant for rosrust
+    let args: Vec<_> = rosrust::args();
+
+    let count = match args.get(1).map(|a| a.parse::<usize>()) {
+        None => 1000,
+        Some(Ok(count)) if count > 0 => count,
+        _ => {
+            println!("usage: add_two_ints_compare [COUNT]");
+            return;
+        }
+    };
+
+    rosrust::init("add_two_ints_compare");
+
+    per_call(count);
+    batch(count);
+    streaming(count);
+}
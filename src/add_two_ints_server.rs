@@ -1,17 +1,52 @@
+use ros_tutorial::audit::Audit;
+use std::path::Path;
+
 mod srv {
-    rosrust::rosmsg_include!(rosservices / AddTwoInts);
+    rosrust::rosmsg_include!(rosservices / AddTwoInts, rosservices / ServiceStats);
 }
 
 fn main() {
     // Initialize node
     rosrust::init("add_two_ints_server");
 
+    // Every request is recorded to audit log, given by argument
+    let log = rosrust::args()
+        .get(1)
+        .cloned()
+        .unwrap_or_else(|| String::from("add_two_ints.audit.jsonl"));
+    let audit = match Audit::new(Some(Path::new(&log))) {
+        Ok(audit) => audit,
+        Err(e) => {
+            rosrust::ros_err!("unable to open audit log {}: {}", log, e);
+            return;
+        }
+    };
+    let _stats_raii = audit
+        .advertise_stats::<srv::rosservices::ServiceStats, _>(|stats| {
+            srv::rosservices::ServiceStatsRes {
+                services: stats.services,
+                requests: stats.requests,
+                errors: stats.errors,
+                mean_ms: stats.mean_ms,
+                max_ms: stats.max_ms,
+            }
+        })
+        .unwrap();
+
     // Create service
     // The service is stopped when the returned object is destroyed
-    let _service_raii =
-        rosrust::service::<srv::rosservices::AddTwoInts, _>("add_two_ints", move |req| {
+    let _service_raii = audit
+        .service::<srv::rosservices::AddTwoInts, _>("add_two_ints", move |req| {
             // Callback for handling requests
-            let sum = req.a + req.b;
+            // Err is sent back to the client as service error
+            let sum = match req.a.checked_add(req.b) {
+                Some(sum) => sum,
+                None => {
+                    let error = format!("{} + {} overflows int64", req.a, req.b);
+                    rosrust::ros_warn!("{}", error);
+                    return Err(error);
+                }
+            };
 
             // Log each request
             rosrust::ros_info!("{} + {} = {}", req.a, req.b, sum);
@@ -20,7 +55,7 @@ fn main() {
         })
         .unwrap();
 
-    rosrust::ros_info!("add_two_ints service running!");
+    rosrust::ros_info!("add_two_ints service running, audit log {}", log);
 
     // Block the thread until a shutdown signal is received
     rosrust::spin();
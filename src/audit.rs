@@ -0,0 +1,232 @@
+//! Audit of service calls: every request, response or error and handling duration
+//! is appended to JSON Lines log and counted per service. Server nodes publish the
+//! counters on their `~stats` service (rosservices/ServiceStats).
+//!
+//! rosrust does not pass the caller id (sent by the client in connection header)
+//! to service handlers, so it is logged as null.
+use crate::fields::quoted;
+use rosrust::{ros_err, Service, ServicePair};
+use std::collections::BTreeMap;
+use std::fmt::Debug;
+use std::fs::{File, OpenOptions};
+use std::io::{self, LineWriter, Write};
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+/// one handled request
+#[derive(Debug, Clone, PartialEq)]
+pub struct AuditRecord {
+    /// seconds since unix epoch
+    pub stamp: f64,
+    pub service: String,
+    pub caller: Option<String>,
+    pub request: String,
+    /// response or error returned to the client
+    pub response: Result<String, String>,
+    pub duration: Duration,
+}
+
+impl AuditRecord {
+    /// single line JSON object, request and response are in Debug format
+    pub fn json(&self) -> String {
+        let (response, error) = match &self.response {
+            Ok(response) => (quoted(response), String::from("null")),
+            Err(error) => (String::from("null"), quoted(error)),
+        };
+        format!(
+            "{{\"stamp_secs\":{:.6},\"service\":{},\"caller\":{},\"request\":{},\"response\":{},\"error\":{},\"duration_ms\":{:.3}}}",
+            self.stamp,
+            quoted(&self.service),
+            self.caller.as_deref().map_or(String::from("null"), quoted),
+            quoted(&self.request),
+            response,
+            error,
+            self.duration.as_secs_f64() * 1000.0
+        )
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Counters {
+    pub requests: u64,
+    pub errors: u64,
+    pub total: Duration,
+    pub max: Duration,
+}
+
+impl Counters {
+    pub fn add(&mut self, record: &AuditRecord) {
+        self.requests += 1;
+        if record.response.is_err() {
+            self.errors += 1;
+        }
+        self.total += record.duration;
+        self.max = self.max.max(record.duration);
+    }
+
+    pub fn mean(&self) -> Duration {
+        match u32::try_from(self.requests) {
+            Ok(0) => Duration::ZERO,
+            Ok(requests) => self.total / requests,
+            Err(_) => Duration::from_secs_f64(self.total.as_secs_f64() / self.requests as f64),
+        }
+    }
+}
+
+/// counters of all services as columns of rosservices/ServiceStats response
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct StatsColumns {
+    pub services: Vec<String>,
+    pub requests: Vec<u64>,
+    pub errors: Vec<u64>,
+    pub mean_ms: Vec<f64>,
+    pub max_ms: Vec<f64>,
+}
+
+/// shared by all audited services of a node
+pub struct Audit {
+    log: Option<Mutex<LineWriter<File>>>,
+    counters: Mutex<BTreeMap<String, Counters>>,
+}
+
+impl Audit {
+    /// audit appending to log file (if given), existing records are kept
+    pub fn new(log: Option<&Path>) -> io::Result<Arc<Audit>> {
+        let log = match log {
+            Some(path) => Some(Mutex::new(LineWriter::new(
+                OpenOptions::new().create(true).append(true).open(path)?,
+            ))),
+            None => None,
+        };
+
+        Ok(Arc::new(Audit {
+            log,
+            counters: Mutex::new(BTreeMap::new()),
+        }))
+    }
+
+    pub fn record(&self, record: &AuditRecord) {
+        self.counters
+            .lock()
+            .unwrap()
+            .entry(record.service.clone())
+            .or_default()
+            .add(record);
+
+        if let Some(log) = &self.log {
+            // line writer flushes each record, so the log is complete even after crash
+            if let Err(e) = writeln!(log.lock().unwrap(), "{}", record.json()) {
+                ros_err!("failed to write audit log: {}", e);
+            }
+        }
+    }
+
+    /// counters by service name
+    pub fn counters(&self) -> BTreeMap<String, Counters> {
+        self.counters.lock().unwrap().clone()
+    }
+
+    pub fn stats(&self) -> StatsColumns {
+        let mut stats = StatsColumns::default();
+        for (service, counters) in self.counters() {
+            stats.services.push(service);
+            stats.requests.push(counters.requests);
+            stats.errors.push(counters.errors);
+            stats.mean_ms.push(counters.mean().as_secs_f64() * 1000.0);
+            stats.max_ms.push(counters.max.as_secs_f64() * 1000.0);
+        }
+        stats
+    }
+
+    /// `~stats` service with counters of all audited services. ServiceStats message is
+    /// generated by the server binaries, they convert the columns to its response.
+    pub fn advertise_stats<T, F>(self: &Arc<Self>, response: F) -> rosrust::error::Result<Service>
+    where
+        T: ServicePair,
+        F: Fn(StatsColumns) -> T::Response + Send + Sync + 'static,
+    {
+        let audit = Arc::clone(self);
+        rosrust::service::<T, _>("~stats", move |_| Ok(response(audit.stats())))
+    }
+
+    /// same as rosrust::service, but each call is recorded
+    pub fn service<T, F>(
+        self: &Arc<Self>,
+        service: &str,
+        handler: F,
+    ) -> rosrust::error::Result<Service>
+    where
+        T: ServicePair,
+        T::Request: Debug,
+        T::Response: Debug,
+        F: Fn(T::Request) -> rosrust::ServiceResult<T::Response> + Send + Sync + 'static,
+    {
+        let audit = Arc::clone(self);
+        let name = service.to_string();
+
+        rosrust::service::<T, _>(service, move |req| {
+            let request = format!("{:?}", req);
+            let start = Instant::now();
+            let result = handler(req);
+
+            audit.record(&AuditRecord {
+                stamp: SystemTime::now()
+                    .duration_since(UNIX_EPOCH)
+                    .unwrap_or_default()
+                    .as_secs_f64(),
+                service: name.clone(),
+                caller: None,
+                request,
+                response: result
+                    .as_ref()
+                    .map(|res| format!("{:?}", res))
+                    .map_err(String::clone),
+                duration: start.elapsed(),
+            });
+
+            result
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_audit() {
+        let mut record = AuditRecord {
+            stamp: 1.5,
+            service: String::from("add_two_ints"),
+            caller: None,
+            request: String::from("AddTwoIntsReq { a: 1, b: 2 }"),
+            response: Ok(String::from("AddTwoIntsRes { sum: 3 }")),
+            duration: Duration::from_millis(2),
+        };
+        assert_eq!(
+            record.json(),
+            "{\"stamp_secs\":1.500000,\"service\":\"add_two_ints\",\"caller\":null,\"request\":\"AddTwoIntsReq { a: 1, b: 2 }\",\"response\":\"AddTwoIntsRes { sum: 3 }\",\"error\":null,\"duration_ms\":2.000}"
+        );
+
+        let audit = Audit::new(None).unwrap();
+        audit.record(&record);
+        record.response = Err(String::from("1 + \"x\" overflows"));
+        record.duration = Duration::from_millis(4);
+        assert!(record
+            .json()
+            .contains("\"response\":null,\"error\":\"1 + \\\"x\\\" overflows\""));
+        audit.record(&record);
+
+        let counters = &audit.counters()["add_two_ints"];
+        assert_eq!(counters.requests, 2);
+        assert_eq!(counters.errors, 1);
+        assert_eq!(counters.mean(), Duration::from_millis(3));
+        assert_eq!(counters.max, Duration::from_millis(4));
+        assert_eq!(Counters::default().mean(), Duration::ZERO);
+        assert_eq!(audit.stats().mean_ms, vec![3.0]);
+
+        record.caller = Some(String::from("/client\r\t"));
+        assert!(record.json().contains("\"caller\":\"/client\\r\\t\""));
+    }
+}
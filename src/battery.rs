@@ -0,0 +1,208 @@
+//! Simulated battery. Charge drains proportionally to commanded linear/angular
+//! velocity and time, state is published as sensor_msgs/BatteryState.
+//!
+//! Battery can be simulated inside the turtle node or alongside the cleaner
+//! (for turtles without battery support, e.g. original turtlesim).
+//!
+//! Topics (for namespace /turtle1):
+//!     /turtle1/cmd_vel       (subscribed) commanded velocity, drives the drain
+//!     /turtle1/charging      (subscribed) std_msgs/Bool, true while turtle is docked
+//!     /turtle1/battery_state (published)  sensor_msgs/BatteryState
+use rosrust::{ros_err, ros_info, Subscriber};
+use rosrust_msg::geometry_msgs::Twist;
+use rosrust_msg::sensor_msgs::BatteryState;
+use rosrust_msg::std_msgs::Bool;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::{Duration, Instant};
+
+/// velocity command is valid only for limited time (same as in turtlesim).
+/// if no new command arrives turtle is considered as stopped.
+const COMMAND_TIMEOUT: Duration = Duration::from_secs(1);
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct BatteryModel {
+    /// remaining charge in range 0.0 (empty) - 1.0 (full)
+    pub charge: f64,
+    /// drain per second when turtle is not moving
+    pub idle_drain: f64,
+    /// drain per second per 1 m/s of linear speed
+    pub linear_drain: f64,
+    /// drain per second per 1 rad/s of angular speed
+    pub angular_drain: f64,
+    /// charge gained per second while charging
+    pub charge_rate: f64,
+    pub charging: bool,
+}
+
+impl Default for BatteryModel {
+    fn default() -> Self {
+        BatteryModel {
+            charge: 1.0,
+            idle_drain: 0.0005,
+            linear_drain: 0.004,
+            angular_drain: 0.002,
+            charge_rate: 0.05,
+            charging: false,
+        }
+    }
+}
+
+impl BatteryModel {
+    /// updates charge after dt seconds of moving by given linear and angular speed
+    pub fn update(&mut self, linear_speed: f64, angular_speed: f64, dt: f64) {
+        if self.charging {
+            self.charge += self.charge_rate * dt;
+        } else {
+            let drain = self.idle_drain
+                + self.linear_drain * linear_speed.abs()
+                + self.angular_drain * angular_speed.abs();
+            self.charge -= drain * dt;
+        }
+
+        self.charge = self.charge.clamp(0.0, 1.0);
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.charge <= 0.0
+    }
+
+    pub fn to_msg(&self) -> BatteryState {
+        let power_supply_status = if self.charging && self.charge >= 1.0 {
+            BatteryState::POWER_SUPPLY_STATUS_FULL
+        } else if self.charging {
+            BatteryState::POWER_SUPPLY_STATUS_CHARGING
+        } else {
+            BatteryState::POWER_SUPPLY_STATUS_DISCHARGING
+        };
+
+        BatteryState {
+            percentage: self.charge as f32,
+            power_supply_status,
+            present: true,
+            ..Default::default()
+        }
+    }
+}
+
+/// keeps battery simulation running. simulation stops when dropped.
+pub struct BatterySimulation {
+    _cmd_vel_subscriber: Subscriber,
+    _charging_subscriber: Subscriber,
+    running: Arc<AtomicBool>,
+}
+
+impl Drop for BatterySimulation {
+    fn drop(&mut self) {
+        self.running.store(false, Ordering::SeqCst);
+    }
+}
+
+/// starts battery simulation for turtle in given namespace (e.g. /turtle1).
+/// battery state is updated and published with given rate (Hz).
+pub fn simulate_battery(namespace: &str, model: BatteryModel, rate: f64) -> BatterySimulation {
+    let model = Arc::new(Mutex::new(model));
+    let last_command = Arc::new(Mutex::new((Twist::default(), Instant::now())));
+    let running = Arc::new(AtomicBool::new(true));
+
+    let command = Arc::clone(&last_command);
+    let cmd_vel_subscriber =
+        rosrust::subscribe(&format!("{}/cmd_vel", namespace), 100, move |t: Twist| {
+            *command.lock().unwrap() = (t, Instant::now());
+        })
+        .unwrap();
+
+    let charging_model = Arc::clone(&model);
+    let charging_subscriber =
+        rosrust::subscribe(&format!("{}/charging", namespace), 10, move |c: Bool| {
+            ros_info!("battery charging: {}", c.data);
+            charging_model.lock().unwrap().charging = c.data;
+        })
+        .unwrap();
+
+    let battery_publisher =
+        rosrust::publish::<BatteryState>(&format!("{}/battery_state", namespace), 10).unwrap();
+
+    let thread_running = Arc::clone(&running);
+    thread::spawn(move || {
+        let loop_rate = rosrust::rate(rate);
+        let mut last_update = Instant::now();
+
+        while rosrust::is_ok() && thread_running.load(Ordering::SeqCst) {
+            let now = Instant::now();
+            let dt = now.duration_since(last_update).as_secs_f64();
+            last_update = now;
+
+            let (linear_speed, angular_speed) = {
+                let (twist, received) = &*last_command.lock().unwrap();
+                if received.elapsed() < COMMAND_TIMEOUT {
+                    (twist.linear.x, twist.angular.z)
+                } else {
+                    (0.0, 0.0)
+                }
+            };
+
+            let msg = {
+                let mut battery = model.lock().unwrap();
+                let was_empty = battery.is_empty();
+                battery.update(linear_speed, angular_speed, dt);
+                if battery.is_empty() && !was_empty {
+                    ros_err!("battery depleted!");
+                }
+                battery.to_msg()
+            };
+
+            battery_publisher.send(msg).unwrap();
+            loop_rate.sleep();
+        }
+    });
+
+    BatterySimulation {
+        _cmd_vel_subscriber: cmd_vel_subscriber,
+        _charging_subscriber: charging_subscriber,
+        running,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_drain_proportional_to_speed() {
+        let mut idle = BatteryModel::default();
+        let mut slow = BatteryModel::default();
+        let mut fast = BatteryModel::default();
+
+        idle.update(0.0, 0.0, 10.0);
+        slow.update(1.0, 0.5, 10.0);
+        fast.update(-2.0, -1.0, 10.0);
+
+        assert!(idle.charge < 1.0);
+        assert!(slow.charge < idle.charge);
+        assert!(fast.charge < slow.charge);
+        assert!(((1.0 - fast.charge) - 10.0 * (0.0005 + 0.008 + 0.002)).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_charging_and_limits() {
+        let mut battery = BatteryModel {
+            charge: 0.01,
+            ..Default::default()
+        };
+        battery.update(2.0, 0.0, 100.0);
+        assert_eq!(battery.charge, 0.0);
+        assert!(battery.is_empty());
+
+        battery.charging = true;
+        battery.update(2.0, 0.0, 10.0);
+        assert!((battery.charge - 0.5).abs() < 1e-9);
+        battery.update(0.0, 0.0, 100.0);
+        assert_eq!(battery.charge, 1.0);
+        assert_eq!(
+            battery.to_msg().power_supply_status,
+            BatteryState::POWER_SUPPLY_STATUS_FULL
+        );
+    }
+}
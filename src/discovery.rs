@@ -0,0 +1,79 @@
+//! Discovery of turtles (and other topics) and services registered at ROS master.
+use rosrust::ros_err;
+
+/// names of all topics of given type (e.g. turtlesim/Pose) known to ROS master, sorted
+pub fn topics_of_type(datatype: &str) -> Vec<String> {
+    match rosrust::topics() {
+        Ok(topics) => {
+            let mut names: Vec<String> = topics
+                .into_iter()
+                .filter(|topic| topic.datatype == datatype)
+                .map(|topic| topic.name)
+                .collect();
+            names.sort();
+            names
+        }
+        Err(e) => {
+            ros_err!("unable to get topics from ROS master: {}", e);
+            vec![]
+        }
+    }
+}
+
+/// names of all services registered at ROS master, sorted
+pub fn service_names() -> Vec<String> {
+    match rosrust::state() {
+        Ok(state) => {
+            let mut names: Vec<String> = state
+                .services
+                .into_iter()
+                .map(|service| service.name)
+                .collect();
+            names.sort();
+            names
+        }
+        Err(e) => {
+            ros_err!("unable to get services from ROS master: {}", e);
+            vec![]
+        }
+    }
+}
+
+/// namespaces of turtles having topic with given name and type, e.g.
+/// turtle_namespaces("cmd_vel", "geometry_msgs/Twist") -> ["/turtle1", "/turtle2"]
+pub fn turtle_namespaces(topic: &str, datatype: &str) -> Vec<String> {
+    namespaces_of(&topics_of_type(datatype), topic)
+}
+
+/// strips /<topic> suffix from topic names. names without the suffix are skipped
+fn namespaces_of(topic_names: &[String], topic: &str) -> Vec<String> {
+    let suffix = format!("/{}", topic);
+    topic_names
+        .iter()
+        .filter_map(|name| name.strip_suffix(&suffix))
+        .filter(|namespace| !namespace.is_empty())
+        .map(String::from)
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_namespaces_of() {
+        let names: Vec<String> = [
+            "/turtle1/cmd_vel",
+            "/turtle2/cmd_vel",
+            "/cmd_vel",
+            "/rosout",
+        ]
+        .iter()
+        .map(|n| n.to_string())
+        .collect();
+        assert_eq!(
+            namespaces_of(&names, "cmd_vel"),
+            vec!["/turtle1", "/turtle2"]
+        );
+    }
+}
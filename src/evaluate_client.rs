@@ -0,0 +1,60 @@
+use std::time;
+
+// rosmsg_include works properly only with linux line end i.e. LF
+// it does now work properly with windows CR LF!!
+mod srv {
+    rosrust::rosmsg_include!(rosservices / Evaluate);
+}
+
+fn main() {
+    // Fetch args that are not meant for rosrust
+    let args: Vec<_> = rosrust::args();
+
+    if args.len() < 2 {
+        println!("usage: evaluate_client EXPRESSION [NAME=VALUE ...]");
+        println!("example: evaluate_client \"sqrt(x^2 + y^2)\" x=3 y=4");
+        return;
+    }
+
+    let expression = args[1].clone();
+    let mut names = vec![];
+    let mut values = vec![];
+    for variable in &args[2..] {
+        match variable
+            .split_once('=')
+            .and_then(|(name, value)| Some((name, value.parse::<f64>().ok()?)))
+        {
+            Some((name, value)) => {
+                names.push(name.to_string());
+                values.push(value);
+            }
+            None => {
+                println!("invalid variable {}, expected NAME=VALUE", variable);
+                return;
+            }
+        }
+    }
+
+    // Initialize node
+    rosrust::init("evaluate_client");
+
+    // Wait ten seconds for the service to appear
+    rosrust::ros_info!("checking for availability of evaluate service...");
+    rosrust::wait_for_service("evaluate", Some(time::Duration::from_secs(10))).unwrap();
+    rosrust::ros_info!("evaluate service found! calling...");
+
+    // Create client for the service
+    let client = rosrust::client::<srv::rosservices::Evaluate>("evaluate").unwrap();
+
+    // Synchronous call that blocks the thread until a response is received
+    // outer Result is the transport error, inner one the error returned by the service
+    match client.req(&srv::rosservices::EvaluateReq {
+        expression: expression.clone(),
+        names,
+        values,
+    }) {
+        Ok(Ok(res)) => rosrust::ros_info!("{} = {}", expression, res.result),
+        Ok(Err(e)) => rosrust::ros_err!("evaluate service error: {}", e),
+        Err(e) => rosrust::ros_err!("evaluate call failed: {}", e),
+    }
+}
@@ -0,0 +1,45 @@
+use ros_tutorial::expression::evaluate;
+use std::collections::HashMap;
+
+// rosmsg_include works properly only with linux line end i.e. LF
+// it does now work properly with windows CR LF!!
+mod srv {
+    rosrust::rosmsg_include!(rosservices / Evaluate);
+}
+
+fn main() {
+    rosrust::init("evaluate_server");
+
+    // Create service
+    // The service is stopped when the returned object is destroyed
+    let _service_raii = rosrust::service::<srv::rosservices::Evaluate, _>("evaluate", move |req| {
+        // Err is sent back to the client as service error
+        if req.names.len() != req.values.len() {
+            let error = format!(
+                "got {} variable names but {} values",
+                req.names.len(),
+                req.values.len()
+            );
+            rosrust::ros_warn!("{}", error);
+            return Err(error);
+        }
+
+        let variables: HashMap<String, f64> = req.names.into_iter().zip(req.values).collect();
+
+        match evaluate(&req.expression, &variables) {
+            Ok(result) => {
+                rosrust::ros_info!("{} = {} {:?}", req.expression, result, variables);
+                Ok(srv::rosservices::EvaluateRes { result })
+            }
+            Err(error) => {
+                rosrust::ros_warn!("{}: {}", req.expression, error);
+                Err(error)
+            }
+        }
+    })
+    .unwrap();
+
+    rosrust::ros_info!("evaluate service running!");
+
+    rosrust::spin();
+}
@@ -0,0 +1,398 @@
+//! Arithmetic expression evaluation (backs the Evaluate service).
+//!
+//! Supported: numbers (1, 2.5, 1e-3), + - * / ^ (power, right associative),
+//! unary minus, parentheses, variables, constants pi and e and functions
+//! sin cos tan asin acos atan sqrt abs exp ln log (base 10) floor ceil round,
+//! min(a, b) max(a, b) atan2(y, x).
+//!
+//! Errors report position (counted from 1) of the offending character.
+use std::collections::HashMap;
+use std::fmt;
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    Number(f64),
+    Name(String),
+    Operator(char),
+    LeftParen,
+    RightParen,
+    Comma,
+}
+
+impl fmt::Display for Token {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Token::Number(number) => write!(f, "number {}", number),
+            Token::Name(name) => write!(f, "'{}'", name),
+            Token::Operator(op) => write!(f, "'{}'", op),
+            Token::LeftParen => write!(f, "'('"),
+            Token::RightParen => write!(f, "')'"),
+            Token::Comma => write!(f, "','"),
+        }
+    }
+}
+
+fn tokenize(expression: &str) -> Result<Vec<(usize, Token)>, String> {
+    let chars: Vec<char> = expression.chars().collect();
+    let mut tokens = vec![];
+    let mut i = 0;
+
+    while i < chars.len() {
+        let c = chars[i];
+        let start = i;
+
+        let token = if c.is_whitespace() {
+            i += 1;
+            continue;
+        } else if c.is_ascii_digit() || c == '.' {
+            while i < chars.len() && (chars[i].is_ascii_digit() || chars[i] == '.') {
+                i += 1;
+            }
+            // exponent, e.g. 1e-3
+            if i < chars.len() && (chars[i] == 'e' || chars[i] == 'E') {
+                let mut j = i + 1;
+                if j < chars.len() && (chars[j] == '+' || chars[j] == '-') {
+                    j += 1;
+                }
+                if j < chars.len() && chars[j].is_ascii_digit() {
+                    i = j;
+                    while i < chars.len() && chars[i].is_ascii_digit() {
+                        i += 1;
+                    }
+                }
+            }
+
+            let text: String = chars[start..i].iter().collect();
+            let number = text
+                .parse::<f64>()
+                .map_err(|_| format!("invalid number '{}' at position {}", text, start + 1))?;
+            tokens.push((start, Token::Number(number)));
+            continue;
+        } else if c.is_alphabetic() || c == '_' {
+            while i < chars.len() && (chars[i].is_alphanumeric() || chars[i] == '_') {
+                i += 1;
+            }
+            tokens.push((start, Token::Name(chars[start..i].iter().collect())));
+            continue;
+        } else {
+            match c {
+                '+' | '-' | '*' | '/' | '^' => Token::Operator(c),
+                '(' => Token::LeftParen,
+                ')' => Token::RightParen,
+                ',' => Token::Comma,
+                _ => return Err(format!("unexpected '{}' at position {}", c, start + 1)),
+            }
+        };
+
+        tokens.push((start, token));
+        i += 1;
+    }
+
+    Ok(tokens)
+}
+
+/// maximal nesting of parentheses, function calls and unary operators. the parser
+/// is recursive, so deeper expressions would overflow the stack
+const MAX_DEPTH: usize = 256;
+
+/// recursive descent parser evaluating the expression while parsing
+struct Parser<'a> {
+    tokens: Vec<(usize, Token)>,
+    next: usize,
+    /// length of the expression, position of the end for error messages
+    end: usize,
+    variables: &'a HashMap<String, f64>,
+    /// current nesting, see MAX_DEPTH
+    depth: usize,
+}
+
+impl<'a> Parser<'a> {
+    /// parses nested part of the expression, fails when nested too deeply
+    fn nested<T>(&mut self, parse: fn(&mut Self) -> Result<T, String>) -> Result<T, String> {
+        if self.depth >= MAX_DEPTH {
+            return Err(String::from("expression nested too deeply"));
+        }
+        self.depth += 1;
+        let result = parse(self);
+        self.depth -= 1;
+        result
+    }
+
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.next).map(|(_, token)| token)
+    }
+
+    fn position(&self) -> usize {
+        self.tokens
+            .get(self.next)
+            .map(|(position, _)| *position)
+            .unwrap_or(self.end)
+            + 1
+    }
+
+    fn unexpected(&self) -> String {
+        match self.peek() {
+            Some(token) => format!("unexpected {} at position {}", token, self.position()),
+            None => String::from("unexpected end of expression"),
+        }
+    }
+
+    fn expect(&mut self, expected: Token) -> Result<(), String> {
+        if self.peek() == Some(&expected) {
+            self.next += 1;
+            Ok(())
+        } else {
+            Err(format!(
+                "expected {} at position {}",
+                expected,
+                self.position()
+            ))
+        }
+    }
+
+    /// expression := term (('+' | '-') term)*
+    fn expression(&mut self) -> Result<f64, String> {
+        let mut value = self.term()?;
+        while let Some(Token::Operator(op @ ('+' | '-'))) = self.peek().cloned() {
+            self.next += 1;
+            let rhs = self.term()?;
+            value = if op == '+' { value + rhs } else { value - rhs };
+        }
+        Ok(value)
+    }
+
+    /// term := unary (('*' | '/') unary)*
+    fn term(&mut self) -> Result<f64, String> {
+        let mut value = self.unary()?;
+        while let Some(Token::Operator(op @ ('*' | '/'))) = self.peek().cloned() {
+            let position = self.position();
+            self.next += 1;
+            let rhs = self.unary()?;
+            value = if op == '*' {
+                value * rhs
+            } else if rhs == 0.0 {
+                return Err(format!("division by zero at position {}", position));
+            } else {
+                value / rhs
+            };
+        }
+        Ok(value)
+    }
+
+    /// unary := ('-' | '+') unary | power
+    fn unary(&mut self) -> Result<f64, String> {
+        match self.peek() {
+            Some(Token::Operator('-')) => {
+                self.next += 1;
+                Ok(-self.nested(Self::unary)?)
+            }
+            Some(Token::Operator('+')) => {
+                self.next += 1;
+                self.nested(Self::unary)
+            }
+            _ => self.power(),
+        }
+    }
+
+    /// power := primary ('^' unary)?   (so that -2^2 = -4 and 2^3^2 = 2^9)
+    fn power(&mut self) -> Result<f64, String> {
+        let base = self.primary()?;
+        if self.peek() == Some(&Token::Operator('^')) {
+            self.next += 1;
+            let exponent = self.nested(Self::unary)?;
+            return Ok(base.powf(exponent));
+        }
+        Ok(base)
+    }
+
+    /// primary := number | name | name '(' arguments ')' | '(' expression ')'
+    fn primary(&mut self) -> Result<f64, String> {
+        let position = self.position();
+        match self.peek().cloned() {
+            Some(Token::Number(number)) => {
+                self.next += 1;
+                Ok(number)
+            }
+            Some(Token::LeftParen) => {
+                self.next += 1;
+                let value = self.nested(Self::expression)?;
+                self.expect(Token::RightParen)?;
+                Ok(value)
+            }
+            Some(Token::Name(name)) => {
+                self.next += 1;
+                if self.peek() == Some(&Token::LeftParen) {
+                    self.next += 1;
+                    let arguments = self.nested(Self::arguments)?;
+                    call(&name, &arguments).map_err(|e| format!("{} at position {}", e, position))
+                } else {
+                    self.variable(&name).ok_or_else(|| {
+                        format!("unknown variable '{}' at position {}", name, position)
+                    })
+                }
+            }
+            _ => Err(self.unexpected()),
+        }
+    }
+
+    /// arguments := expression (',' expression)* ')'
+    fn arguments(&mut self) -> Result<Vec<f64>, String> {
+        let mut arguments = vec![self.expression()?];
+        while self.peek() == Some(&Token::Comma) {
+            self.next += 1;
+            arguments.push(self.expression()?);
+        }
+        self.expect(Token::RightParen)?;
+        Ok(arguments)
+    }
+
+    fn variable(&self, name: &str) -> Option<f64> {
+        match self.variables.get(name) {
+            Some(value) => Some(*value),
+            None => match name {
+                "pi" => Some(std::f64::consts::PI),
+                "e" => Some(std::f64::consts::E),
+                _ => None,
+            },
+        }
+    }
+}
+
+fn call(function: &str, arguments: &[f64]) -> Result<f64, String> {
+    let unary: Option<fn(f64) -> f64> = match function {
+        "sin" => Some(f64::sin),
+        "cos" => Some(f64::cos),
+        "tan" => Some(f64::tan),
+        "asin" => Some(f64::asin),
+        "acos" => Some(f64::acos),
+        "atan" => Some(f64::atan),
+        "sqrt" => Some(f64::sqrt),
+        "abs" => Some(f64::abs),
+        "exp" => Some(f64::exp),
+        "ln" => Some(f64::ln),
+        "log" => Some(f64::log10),
+        "floor" => Some(f64::floor),
+        "ceil" => Some(f64::ceil),
+        "round" => Some(f64::round),
+        _ => None,
+    };
+    let binary: Option<fn(f64, f64) -> f64> = match function {
+        "min" => Some(f64::min),
+        "max" => Some(f64::max),
+        "atan2" => Some(f64::atan2),
+        _ => None,
+    };
+
+    match (unary, binary, arguments) {
+        (Some(f), _, [x]) => Ok(f(*x)),
+        (_, Some(f), [a, b]) => Ok(f(*a, *b)),
+        (Some(_), _, _) => Err(format!("{} expects 1 argument", function)),
+        (_, Some(_), _) => Err(format!("{} expects 2 arguments", function)),
+        _ => Err(format!("unknown function '{}'", function)),
+    }
+}
+
+/// evaluates the expression with given variables
+pub fn evaluate(expression: &str, variables: &HashMap<String, f64>) -> Result<f64, String> {
+    let mut parser = Parser {
+        tokens: tokenize(expression)?,
+        next: 0,
+        end: expression.chars().count(),
+        variables,
+        depth: 0,
+    };
+
+    if parser.peek().is_none() {
+        return Err(String::from("empty expression"));
+    }
+
+    let value = parser.expression()?;
+    if parser.peek().is_some() {
+        return Err(parser.unexpected());
+    }
+
+    if value.is_finite() {
+        Ok(value)
+    } else {
+        Err(format!("result is not a finite number ({})", value))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn eval(expression: &str) -> Result<f64, String> {
+        let variables = [(String::from("x"), 3.0), (String::from("width"), 0.5)]
+            .into_iter()
+            .collect();
+        evaluate(expression, &variables)
+    }
+
+    #[test]
+    fn test_evaluate() {
+        assert_eq!(eval("1 + 2 * 3"), Ok(7.0));
+        assert_eq!(eval("(1 + 2) * 3"), Ok(9.0));
+        assert_eq!(eval("2 ^ 3 ^ 2"), Ok(512.0));
+        assert_eq!(eval("-2^2"), Ok(-4.0));
+        assert_eq!(eval("10 / 4 - -1"), Ok(3.5));
+        assert_eq!(eval("1.5e2 + 2E-1"), Ok(150.2));
+        assert_eq!(eval("x * width + max(1, x)"), Ok(4.5));
+        assert_eq!(eval("sqrt(16) + abs(-2) + floor(2.7)"), Ok(8.0));
+        assert!((eval("sin(pi / 2) + ln(e)").unwrap() - 2.0).abs() < 1e-12);
+        assert!((eval("atan2(1, 1)").unwrap() - std::f64::consts::FRAC_PI_4).abs() < 1e-12);
+    }
+
+    #[test]
+    fn test_errors() {
+        assert_eq!(
+            eval("1 / (x - 3)"),
+            Err(String::from("division by zero at position 3"))
+        );
+        assert_eq!(
+            eval("2 * y"),
+            Err(String::from("unknown variable 'y' at position 5"))
+        );
+        assert_eq!(
+            eval("1 + $"),
+            Err(String::from("unexpected '$' at position 5"))
+        );
+        assert_eq!(
+            eval("(1 + 2"),
+            Err(String::from("expected ')' at position 7"))
+        );
+        assert_eq!(
+            eval("1 +"),
+            Err(String::from("unexpected end of expression"))
+        );
+        assert_eq!(
+            eval("1 2"),
+            Err(String::from("unexpected number 2 at position 3"))
+        );
+        assert!(eval("foo(1)")
+            .unwrap_err()
+            .starts_with("unknown function 'foo'"));
+        assert!(eval("min(1)")
+            .unwrap_err()
+            .starts_with("min expects 2 arguments"));
+        assert!(eval("sqrt(-1)").is_err());
+
+        let nested = |open: &str, depth: usize, close: &str| {
+            format!("{}1{}", open.repeat(depth), close.repeat(depth))
+        };
+        assert_eq!(eval(&nested("(", 200, ")")), Ok(1.0));
+        assert_eq!(eval(&nested("-", 200, "")), Ok(1.0));
+        for expression in [
+            nested("(", 100000, ")"),
+            nested("-", 100000, ""),
+            nested("abs(", 100000, ")"),
+            nested("2^", 100000, ""),
+        ] {
+            assert_eq!(
+                eval(&expression),
+                Err(String::from("expression nested too deeply"))
+            );
+        }
+        assert!(eval("  ").is_err());
+    }
+}
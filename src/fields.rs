@@ -0,0 +1,365 @@
+//! Message fields as loosely typed values, used by ros-call to build requests from
+//! command line (key=value) or YAML and to print responses as YAML or JSON.
+//!
+//! Only flat messages are supported: scalars and lists of scalars, which is
+//! enough for the project's services.
+use std::fmt;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Value {
+    Bool(bool),
+    Int(i64),
+    Float(f64),
+    Str(String),
+    List(Vec<Value>),
+}
+
+/// field name and value, in order given by the user or message definition
+pub type Fields = Vec<(String, Value)>;
+
+/// double quoted string, the escapes are same in JSON and YAML
+pub fn quoted(s: &str) -> String {
+    let mut quoted = String::with_capacity(s.len() + 2);
+    quoted.push('"');
+    for c in s.chars() {
+        match c {
+            '"' => quoted.push_str("\\\""),
+            '\\' => quoted.push_str("\\\\"),
+            '\n' => quoted.push_str("\\n"),
+            '\r' => quoted.push_str("\\r"),
+            '\t' => quoted.push_str("\\t"),
+            c if c.is_control() => quoted.push_str(&format!("\\u{:04x}", c as u32)),
+            c => quoted.push(c),
+        }
+    }
+    quoted.push('"');
+    quoted
+}
+
+impl Value {
+    /// value from text: number, true/false, [flow, list], "quoted" or plain string
+    pub fn parse(text: &str) -> Value {
+        let text = text.trim();
+
+        if let Some(items) = text.strip_prefix('[').and_then(|t| t.strip_suffix(']')) {
+            if items.trim().is_empty() {
+                return Value::List(vec![]);
+            }
+            return Value::List(items.split(',').map(Value::parse).collect());
+        }
+
+        for quote in ['"', '\''] {
+            if text.len() >= 2 && text.starts_with(quote) && text.ends_with(quote) {
+                return Value::Str(text[1..text.len() - 1].to_string());
+            }
+        }
+
+        if let Ok(int) = text.parse::<i64>() {
+            Value::Int(int)
+        } else if let Ok(float) = text.parse::<f64>() {
+            Value::Float(float)
+        } else if let Ok(bool) = text.parse::<bool>() {
+            Value::Bool(bool)
+        } else {
+            Value::Str(text.to_string())
+        }
+    }
+
+    fn to_json(&self) -> String {
+        match self {
+            Value::Str(s) => quoted(s),
+            Value::Float(f) if !f.is_finite() => String::from("null"),
+            Value::List(items) => format!(
+                "[{}]",
+                items
+                    .iter()
+                    .map(Value::to_json)
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            ),
+            _ => self.to_string(),
+        }
+    }
+}
+
+impl fmt::Display for Value {
+    /// YAML (flow style) representation
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Value::Bool(b) => write!(f, "{}", b),
+            Value::Int(i) => write!(f, "{}", i),
+            // keep decimal point, so that the value reads back as float
+            Value::Float(x) if x.fract() == 0.0 && x.is_finite() => write!(f, "{:.1}", x),
+            Value::Float(x) => write!(f, "{}", x),
+            Value::Str(s) => write!(f, "{}", quoted(s)),
+            Value::List(items) => {
+                let items: Vec<String> = items.iter().map(Value::to_string).collect();
+                write!(f, "[{}]", items.join(", "))
+            }
+        }
+    }
+}
+
+/// fields from key=value arguments
+pub fn parse_assignments(args: &[String]) -> Result<Fields, String> {
+    args.iter()
+        .map(|arg| match arg.split_once('=') {
+            Some((key, value)) if !key.trim().is_empty() => {
+                Ok((key.trim().to_string(), Value::parse(value)))
+            }
+            _ => Err(format!("invalid argument '{}', expected key=value", arg)),
+        })
+        .collect()
+}
+
+/// fields from YAML mapping of scalars and lists (flow [a, b] or block "- a" items)
+pub fn parse_yaml(content: &str) -> Result<Fields, String> {
+    let mut fields: Fields = vec![];
+    // key waiting for block list items
+    let mut list: Option<(String, Vec<Value>)> = None;
+
+    for (i, line) in content.lines().enumerate() {
+        let trimmed = line.trim();
+        if trimmed.is_empty() || trimmed.starts_with('#') || trimmed == "---" {
+            continue;
+        }
+
+        if let Some(item) = trimmed.strip_prefix("- ") {
+            match &mut list {
+                Some((_, items)) => items.push(Value::parse(item)),
+                None => return Err(format!("line {}: list item without key", i + 1)),
+            }
+            continue;
+        }
+
+        if let Some((key, items)) = list.take() {
+            fields.push((key, Value::List(items)));
+        }
+
+        match trimmed.split_once(':') {
+            Some((key, value)) if !key.trim().is_empty() => {
+                if value.trim().is_empty() {
+                    list = Some((key.trim().to_string(), vec![]));
+                } else {
+                    fields.push((key.trim().to_string(), Value::parse(value)));
+                }
+            }
+            _ => return Err(format!("line {}: expected key: value", i + 1)),
+        }
+    }
+
+    if let Some((key, items)) = list {
+        fields.push((key, Value::List(items)));
+    }
+    Ok(fields)
+}
+
+pub fn to_yaml(fields: &Fields) -> String {
+    fields
+        .iter()
+        .map(|(key, value)| format!("{}: {}\n", key, value))
+        .collect()
+}
+
+pub fn to_json(fields: &Fields) -> String {
+    let fields: Vec<String> = fields
+        .iter()
+        .map(|(key, value)| format!("\"{}\": {}", key, value.to_json()))
+        .collect();
+    format!("{{{}}}", fields.join(", "))
+}
+
+/// conversion of value to message field type
+pub trait FromValue: Sized {
+    fn from_value(value: &Value) -> Result<Self, String>;
+}
+
+/// conversion of message field to value
+pub trait ToValue {
+    fn to_value(&self) -> Value;
+}
+
+macro_rules! integer_value {
+    ($($t:ty),*) => {$(
+        impl FromValue for $t {
+            fn from_value(value: &Value) -> Result<Self, String> {
+                match value {
+                    Value::Int(i) => <$t>::try_from(*i)
+                        .map_err(|_| format!("{} is out of range of {}", i, stringify!($t))),
+                    other => Err(format!("expected integer, got {}", other)),
+                }
+            }
+        }
+
+        impl ToValue for $t {
+            fn to_value(&self) -> Value {
+                Value::Int(*self as i64)
+            }
+        }
+    )*};
+}
+
+integer_value!(i8, i16, i32, i64, u8, u16, u32);
+
+impl FromValue for u64 {
+    fn from_value(value: &Value) -> Result<Self, String> {
+        match value {
+            Value::Int(i) => u64::try_from(*i).map_err(|_| format!("{} is out of range of u64", i)),
+            other => Err(format!("expected integer, got {}", other)),
+        }
+    }
+}
+
+impl ToValue for u64 {
+    fn to_value(&self) -> Value {
+        match i64::try_from(*self) {
+            Ok(i) => Value::Int(i),
+            Err(_) => Value::Float(*self as f64),
+        }
+    }
+}
+
+impl FromValue for f64 {
+    fn from_value(value: &Value) -> Result<Self, String> {
+        match value {
+            Value::Float(f) => Ok(*f),
+            Value::Int(i) => Ok(*i as f64),
+            other => Err(format!("expected number, got {}", other)),
+        }
+    }
+}
+
+impl ToValue for f64 {
+    fn to_value(&self) -> Value {
+        Value::Float(*self)
+    }
+}
+
+impl FromValue for f32 {
+    fn from_value(value: &Value) -> Result<Self, String> {
+        f64::from_value(value).map(|f| f as f32)
+    }
+}
+
+impl ToValue for f32 {
+    fn to_value(&self) -> Value {
+        Value::Float(*self as f64)
+    }
+}
+
+impl FromValue for bool {
+    fn from_value(value: &Value) -> Result<Self, String> {
+        match value {
+            Value::Bool(b) => Ok(*b),
+            other => Err(format!("expected true or false, got {}", other)),
+        }
+    }
+}
+
+impl ToValue for bool {
+    fn to_value(&self) -> Value {
+        Value::Bool(*self)
+    }
+}
+
+impl FromValue for String {
+    fn from_value(value: &Value) -> Result<Self, String> {
+        match value {
+            Value::Str(s) => Ok(s.clone()),
+            Value::List(_) => Err(format!("expected string, got {}", value)),
+            // e.g. expression=42 is parsed as number
+            scalar => Ok(scalar.to_string()),
+        }
+    }
+}
+
+impl ToValue for String {
+    fn to_value(&self) -> Value {
+        Value::Str(self.clone())
+    }
+}
+
+impl<T: FromValue> FromValue for Vec<T> {
+    fn from_value(value: &Value) -> Result<Self, String> {
+        match value {
+            Value::List(items) => items.iter().map(T::from_value).collect(),
+            other => Err(format!("expected list, got {}", other)),
+        }
+    }
+}
+
+impl<T: ToValue> ToValue for Vec<T> {
+    fn to_value(&self) -> Value {
+        Value::List(self.iter().map(T::to_value).collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse() {
+        let args: Vec<String> = ["a=1", "x=[0, 2.5]", "kind=polygon", "e=\"42\""]
+            .iter()
+            .map(|a| a.to_string())
+            .collect();
+        let fields = parse_assignments(&args).unwrap();
+        assert_eq!(
+            fields,
+            vec![
+                (String::from("a"), Value::Int(1)),
+                (
+                    String::from("x"),
+                    Value::List(vec![Value::Int(0), Value::Float(2.5)])
+                ),
+                (String::from("kind"), Value::Str(String::from("polygon"))),
+                (String::from("e"), Value::Str(String::from("42"))),
+            ]
+        );
+        assert!(parse_assignments(&[String::from("a")]).is_err());
+
+        let yaml = "# request\nkind: polygon\nx: [0, 1, 1]\ny:\n  - 0\n  - 0\n  - 1\nsides: 3\n";
+        let fields = parse_yaml(yaml).unwrap();
+        assert_eq!(fields.len(), 4);
+        assert_eq!(
+            fields[2],
+            (
+                String::from("y"),
+                Value::List(vec![Value::Int(0), Value::Int(0), Value::Int(1)])
+            )
+        );
+        assert!(parse_yaml("- 1").is_err());
+    }
+
+    #[test]
+    fn test_conversions() {
+        assert_eq!(i64::from_value(&Value::Int(-3)), Ok(-3));
+        assert!(u32::from_value(&Value::Int(-3)).is_err());
+        assert_eq!(f64::from_value(&Value::Int(2)), Ok(2.0));
+        assert_eq!(String::from_value(&Value::Int(42)), Ok(String::from("42")));
+        assert_eq!(
+            Vec::<f64>::from_value(&Value::parse("[1, 2.5]")),
+            Ok(vec![1.0, 2.5])
+        );
+        assert!(i64::from_value(&Value::Float(1.5)).is_err());
+
+        let fields = vec![
+            (String::from("area"), 4.0.to_value()),
+            (String::from("sums"), vec![1i64, 2].to_value()),
+            (String::from("error"), String::from("a \"b\"").to_value()),
+        ];
+        assert_eq!(
+            to_yaml(&fields),
+            "area: 4.0\nsums: [1, 2]\nerror: \"a \\\"b\\\"\"\n"
+        );
+        assert_eq!(
+            to_json(&fields),
+            "{\"area\": 4.0, \"sums\": [1, 2], \"error\": \"a \\\"b\\\"\"}"
+        );
+
+        let path = Value::Str(String::from("C:\\tmp\tx\u{1}"));
+        assert_eq!(path.to_string(), "\"C:\\\\tmp\\tx\\u0001\"");
+        assert_eq!(path.to_json(), path.to_string());
+    }
+}
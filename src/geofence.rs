@@ -0,0 +1,282 @@
+//! Geofence: allowed zones and keep-out zones given as polygons.
+//!
+//! Turtle violates the geofence when it is outside all allowed zones (if any are
+//! defined) or inside a keep-out zone. Violation is cleared only after the turtle
+//! gets back at least `margin` from the zone border, so a turtle driving along
+//! the border does not raise alert after alert.
+//!
+//! Config file format, one zone per line (see res/config/geofence.conf):
+//!
+//! ```text
+//! margin 0.3
+//! allowed <name> x1,y1 x2,y2 x3,y3 ...
+//! keepout <name> x1,y1 x2,y2 x3,y3 ...
+//! ```
+use std::collections::BTreeSet;
+use std::fs;
+use std::path::Path;
+
+/// name of the violation of leaving (all) allowed zones
+pub const ALLOWED_AREA: &str = "allowed area";
+
+/// default hysteresis margin
+const DEFAULT_MARGIN: f64 = 0.3;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ZoneKind {
+    Allowed,
+    KeepOut,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Zone {
+    pub name: String,
+    pub kind: ZoneKind,
+    pub polygon: Vec<(f64, f64)>,
+}
+
+impl Zone {
+    /// distance of the point from the zone border, positive inside, negative outside
+    pub fn signed_distance(&self, x: f64, y: f64) -> f64 {
+        let n = self.polygon.len();
+        let mut inside = false;
+        let mut distance = f64::INFINITY;
+
+        for i in 0..n {
+            let (x1, y1) = self.polygon[i];
+            let (x2, y2) = self.polygon[(i + 1) % n];
+
+            // ray casting to the right
+            if (y1 > y) != (y2 > y) && x < x1 + (y - y1) / (y2 - y1) * (x2 - x1) {
+                inside = !inside;
+            }
+
+            // distance to the edge segment
+            let (dx, dy) = (x2 - x1, y2 - y1);
+            let length_squared = dx * dx + dy * dy;
+            let t = if length_squared > 0.0 {
+                (((x - x1) * dx + (y - y1) * dy) / length_squared).clamp(0.0, 1.0)
+            } else {
+                0.0
+            };
+            distance = distance.min((x - (x1 + t * dx)).hypot(y - (y1 + t * dy)));
+        }
+
+        if inside {
+            distance
+        } else {
+            -distance
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Geofence {
+    pub zones: Vec<Zone>,
+    /// hysteresis, violation is cleared this far from the border
+    pub margin: f64,
+}
+
+impl Geofence {
+    pub fn parse(content: &str) -> Result<Geofence, String> {
+        let mut geofence = Geofence {
+            zones: vec![],
+            margin: DEFAULT_MARGIN,
+        };
+
+        for (i, line) in content.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let error = |message: &str| format!("line {}: {}", i + 1, message);
+
+            let fields: Vec<&str> = line.split_whitespace().collect();
+            let kind = match fields[0] {
+                "margin" => {
+                    geofence.margin = match fields[1..] {
+                        [margin] => margin.parse().map_err(|_| error("invalid margin"))?,
+                        _ => return Err(error("expected margin <distance>")),
+                    };
+                    continue;
+                }
+                "allowed" => ZoneKind::Allowed,
+                "keepout" => ZoneKind::KeepOut,
+                other => return Err(error(&format!("unknown zone kind {}", other))),
+            };
+
+            if fields.len() < 5 {
+                return Err(error("expected <kind> <name> and at least 3 points"));
+            }
+
+            let polygon = fields[2..]
+                .iter()
+                .map(|point| {
+                    let (x, y) = point.split_once(',')?;
+                    Some((x.parse::<f64>().ok()?, y.parse::<f64>().ok()?))
+                })
+                .collect::<Option<_>>()
+                .ok_or_else(|| error("points must be x,y"))?;
+
+            geofence.zones.push(Zone {
+                name: fields[1].to_string(),
+                kind,
+                polygon,
+            });
+        }
+
+        Ok(geofence)
+    }
+
+    pub fn load(path: &Path) -> Result<Geofence, String> {
+        let content = fs::read_to_string(path).map_err(|e| format!("{:?}: {}", path, e))?;
+        Geofence::parse(&content)
+    }
+}
+
+/// change of violation state
+#[derive(Debug, Clone, PartialEq)]
+pub struct Alert {
+    /// keep-out zone name or ALLOWED_AREA
+    pub zone: String,
+    /// true when violation starts, false when it is cleared
+    pub active: bool,
+}
+
+impl Alert {
+    pub fn message(&self, turtle: &str) -> String {
+        match (self.zone == ALLOWED_AREA, self.active) {
+            (true, true) => format!("{} left {}", turtle, ALLOWED_AREA),
+            (true, false) => format!("{} is back in {}", turtle, ALLOWED_AREA),
+            (false, true) => format!("{} entered keep-out zone {}", turtle, self.zone),
+            (false, false) => format!("{} left keep-out zone {}", turtle, self.zone),
+        }
+    }
+}
+
+/// violations of one turtle
+#[derive(Debug, Clone, Default)]
+pub struct GeofenceState {
+    violations: BTreeSet<String>,
+}
+
+impl GeofenceState {
+    /// updates violations by the new turtle position, returns alerts for changed ones
+    pub fn update(&mut self, geofence: &Geofence, x: f64, y: f64) -> Vec<Alert> {
+        let mut alerts = vec![];
+        let mut set = |zone: &str, violated: bool| {
+            let changed = if violated {
+                self.violations.insert(zone.to_string())
+            } else {
+                self.violations.remove(zone)
+            };
+            if changed {
+                alerts.push(Alert {
+                    zone: zone.to_string(),
+                    active: violated,
+                });
+            }
+        };
+
+        // allowed zones act as one area, turtle can move between them
+        let allowed = geofence
+            .zones
+            .iter()
+            .filter(|zone| zone.kind == ZoneKind::Allowed)
+            .map(|zone| zone.signed_distance(x, y))
+            .reduce(f64::max);
+        if let Some(distance) = allowed {
+            if distance < 0.0 {
+                set(ALLOWED_AREA, true);
+            } else if distance > geofence.margin {
+                set(ALLOWED_AREA, false);
+            }
+        }
+
+        for zone in geofence
+            .zones
+            .iter()
+            .filter(|zone| zone.kind == ZoneKind::KeepOut)
+        {
+            let distance = zone.signed_distance(x, y);
+            if distance > 0.0 {
+                set(&zone.name, true);
+            } else if distance < -geofence.margin {
+                set(&zone.name, false);
+            }
+        }
+
+        alerts
+    }
+
+    /// names of currently violated zones
+    pub fn violations(&self) -> impl Iterator<Item = &str> {
+        self.violations.iter().map(String::as_str)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn geofence() -> Geofence {
+        Geofence::parse(
+            "# test\nmargin 0.5\nallowed arena 1,1 10,1 10,10 1,10\nkeepout pond 4,4 6,4 6,6 4,6\n",
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn test_parse_and_distance() {
+        let geofence = geofence();
+        assert_eq!(geofence.margin, 0.5);
+        assert_eq!(geofence.zones.len(), 2);
+        assert_eq!(geofence.zones[1].kind, ZoneKind::KeepOut);
+
+        let pond = &geofence.zones[1];
+        assert!((pond.signed_distance(5.0, 5.0) - 1.0).abs() < 1e-9);
+        assert!((pond.signed_distance(7.0, 5.0) + 1.0).abs() < 1e-9);
+        assert!((pond.signed_distance(7.0, 7.0) + 2f64.sqrt()).abs() < 1e-9);
+
+        assert!(Geofence::parse("keepout pond 4,4 6,4").is_err());
+        assert!(Geofence::parse("keepout pond 4,4 6,4 6;6").is_err());
+        assert!(Geofence::parse("forbidden pond 4,4 6,4 6,6").is_err());
+    }
+
+    #[test]
+    fn test_hysteresis() {
+        let geofence = geofence();
+        let mut state = GeofenceState::default();
+
+        assert!(state.update(&geofence, 2.0, 2.0).is_empty());
+
+        // entering the pond
+        let alerts = state.update(&geofence, 4.1, 5.0);
+        assert_eq!(
+            alerts,
+            vec![Alert {
+                zone: String::from("pond"),
+                active: true
+            }]
+        );
+        assert_eq!(
+            alerts[0].message("/turtle1"),
+            "/turtle1 entered keep-out zone pond"
+        );
+
+        // just outside the border, still within margin
+        assert!(state.update(&geofence, 3.8, 5.0).is_empty());
+        assert!(state.update(&geofence, 4.05, 5.0).is_empty());
+        assert_eq!(state.violations().collect::<Vec<_>>(), vec!["pond"]);
+
+        let alerts = state.update(&geofence, 3.4, 5.0);
+        assert!(!alerts[0].active);
+
+        // leaving the arena
+        let alerts = state.update(&geofence, 0.9, 5.0);
+        assert_eq!(alerts[0].zone, ALLOWED_AREA);
+        assert!(state.update(&geofence, 1.2, 5.0).is_empty());
+        assert_eq!(state.update(&geofence, 1.6, 5.0).len(), 1);
+        assert_eq!(state.violations().count(), 0);
+    }
+}
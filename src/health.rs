@@ -0,0 +1,169 @@
+//! Health of services checked by canary requests (requests with known response).
+use std::time::Duration;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Level {
+    Ok,
+    /// service works, but responds slowly
+    Warn,
+    Error,
+}
+
+impl Level {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Level::Ok => "OK",
+            Level::Warn => "WARN",
+            Level::Error => "ERROR",
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct CheckResult {
+    pub service: String,
+    pub level: Level,
+    pub message: String,
+    /// None when the service was not called
+    pub latency: Option<Duration>,
+}
+
+impl CheckResult {
+    /// service is not registered at ROS master, so it is not called at all
+    pub fn unregistered(service: &str) -> CheckResult {
+        CheckResult {
+            service: service.to_string(),
+            level: Level::Error,
+            message: String::from("not registered at ROS master"),
+            latency: None,
+        }
+    }
+
+    /// result of canary call, Err describes failed call or unexpected response.
+    /// response slower than `slow` is a warning.
+    pub fn from_call(
+        service: &str,
+        outcome: Result<(), String>,
+        latency: Duration,
+        slow: Duration,
+    ) -> CheckResult {
+        let (level, message) = match outcome {
+            Ok(()) if latency > slow => (
+                Level::Warn,
+                format!("slow response, over {} ms", slow.as_millis()),
+            ),
+            Ok(()) => (Level::Ok, String::new()),
+            Err(e) => (Level::Error, e),
+        };
+
+        CheckResult {
+            service: service.to_string(),
+            level,
+            message,
+            latency: Some(latency),
+        }
+    }
+}
+
+/// the worst level of all results
+pub fn overall(results: &[CheckResult]) -> Level {
+    results
+        .iter()
+        .map(|result| result.level)
+        .max()
+        .unwrap_or(Level::Ok)
+}
+
+/// names in current but not in previous list and names in previous but not in current list
+pub fn changes(previous: &[String], current: &[String]) -> (Vec<String>, Vec<String>) {
+    let missing_in = |names: &[String], other: &[String]| {
+        names
+            .iter()
+            .filter(|name| !other.contains(name))
+            .cloned()
+            .collect()
+    };
+    (missing_in(current, previous), missing_in(previous, current))
+}
+
+/// text table of check results
+pub fn summary(results: &[CheckResult], services: usize, topics: usize) -> Vec<String> {
+    let mut lines = vec![
+        format!(
+            "health {}, master: {} services, {} topics",
+            overall(results).name(),
+            services,
+            topics
+        ),
+        format!(
+            "{:<20} {:<6} {:>10}  {}",
+            "service", "status", "latency", "message"
+        ),
+    ];
+
+    for result in results {
+        let latency = match result.latency {
+            Some(latency) => format!("{:.1} ms", latency.as_secs_f64() * 1000.0),
+            None => String::from("-"),
+        };
+        lines.push(
+            format!(
+                "{:<20} {:<6} {:>10}  {}",
+                result.service,
+                result.level.name(),
+                latency,
+                result.message
+            )
+            .trim_end()
+            .to_string(),
+        );
+    }
+    lines
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_health() {
+        let slow = Duration::from_millis(500);
+        let results = vec![
+            CheckResult::from_call("/add_two_ints", Ok(()), Duration::from_millis(2), slow),
+            CheckResult::from_call("/evaluate", Ok(()), Duration::from_secs(1), slow),
+        ];
+        assert_eq!(results[0].level, Level::Ok);
+        assert_eq!(results[1].level, Level::Warn);
+        assert_eq!(overall(&results), Level::Warn);
+
+        let results = vec![
+            results[0].clone(),
+            CheckResult::unregistered("/rect_area_service"),
+            CheckResult::from_call(
+                "/shape_metrics",
+                Err(String::from("area = 5, expected 6")),
+                Duration::from_millis(1),
+                slow,
+            ),
+        ];
+        assert_eq!(overall(&results), Level::Error);
+        assert_eq!(overall(&[]), Level::Ok);
+        assert_eq!(
+            summary(&results, 12, 8),
+            vec![
+                "health ERROR, master: 12 services, 8 topics",
+                "service              status    latency  message",
+                "/add_two_ints        OK         2.0 ms",
+                "/rect_area_service   ERROR           -  not registered at ROS master",
+                "/shape_metrics       ERROR      1.0 ms  area = 5, expected 6",
+            ]
+        );
+
+        let previous: Vec<String> = vec![String::from("/a"), String::from("/b")];
+        let current: Vec<String> = vec![String::from("/b"), String::from("/c")];
+        assert_eq!(
+            changes(&previous, &current),
+            (vec![String::from("/c")], vec![String::from("/a")])
+        );
+    }
+}
@@ -0,0 +1,192 @@
+use ros_tutorial::discovery::service_names;
+use ros_tutorial::health::{changes, summary, CheckResult, Level};
+use ros_tutorial::service_client::{CallError, CallPolicy, ServiceClient};
+use rosrust::{ros_info, ros_warn};
+use rosrust_msg::diagnostic_msgs::{DiagnosticArray, DiagnosticStatus, KeyValue};
+use std::fmt::Display;
+use std::time::{Duration, Instant};
+
+// rosmsg_include works properly only with linux line end i.e. LF
+// it does now work properly with windows CR LF!!
+mod srv {
+    rosrust::rosmsg_include!(
+        rosservices / AddTwoInts,
+        rosservices / RectArea,
+        rosservices / Evaluate,
+        rosservices / ShapeMetrics
+    );
+}
+
+use srv::rosservices::*;
+
+/// seconds between checks, unless given by argument
+const DEFAULT_PERIOD: f64 = 5.0;
+
+/// response slower than this is reported as warning
+const SLOW_RESPONSE: Duration = Duration::from_millis(500);
+
+/// request with known response
+struct Canary {
+    service: &'static str,
+    check: Box<dyn FnMut() -> Result<(), String>>,
+}
+
+/// Err when the call failed or returned other than expected value
+fn expect<T: PartialEq + Display>(
+    result: Result<T, CallError>,
+    expected: T,
+    what: &str,
+) -> Result<(), String> {
+    match result {
+        Ok(value) if value == expected => Ok(()),
+        Ok(value) => Err(format!("{} = {}, expected {}", what, value, expected)),
+        Err(e) => Err(e.to_string()),
+    }
+}
+
+fn canaries() -> Vec<Canary> {
+    // check must fail well before clients give up on the service, so
+    // calls are short and not retried (the next check is the retry)
+    let policy = CallPolicy {
+        connect_timeout: Duration::from_millis(500),
+        call_timeout: Duration::from_secs(1),
+        retries: 0,
+        ..Default::default()
+    };
+
+    let mut add = ServiceClient::<AddTwoInts>::new("/add_two_ints", policy.clone());
+    let mut area = ServiceClient::<RectArea>::new("/rect_area_service", policy.clone());
+    let mut evaluate = ServiceClient::<Evaluate>::new("/evaluate", policy.clone());
+    let mut shape = ServiceClient::<ShapeMetrics>::new("/shape_metrics", policy);
+
+    vec![
+        Canary {
+            service: "/add_two_ints",
+            check: Box::new(move || {
+                let res = add.call(&AddTwoIntsReq { a: 1, b: 1 });
+                expect(res.map(|res| res.sum), 2, "1 + 1")
+            }),
+        },
+        Canary {
+            service: "/rect_area_service",
+            check: Box::new(move || {
+                let res = area.call(&RectAreaReq {
+                    width: 2,
+                    length: 3,
+                });
+                expect(res.map(|res| res.area), 6, "2 * 3")
+            }),
+        },
+        Canary {
+            service: "/evaluate",
+            check: Box::new(move || {
+                let res = evaluate.call(&EvaluateReq {
+                    expression: String::from("1 + 2 * x"),
+                    names: vec![String::from("x")],
+                    values: vec![3.0],
+                });
+                expect(res.map(|res| res.result), 7.0, "1 + 2 * 3")
+            }),
+        },
+        Canary {
+            service: "/shape_metrics",
+            check: Box::new(move || {
+                let res = shape.call(&ShapeMetricsReq {
+                    kind: String::from("rectangle"),
+                    width: 2.0,
+                    length: 3.0,
+                    ..Default::default()
+                });
+                expect(res.map(|res| res.area), 6.0, "area of 2 x 3 rectangle")
+            }),
+        },
+    ]
+}
+
+fn diagnostic_status(result: &CheckResult) -> DiagnosticStatus {
+    DiagnosticStatus {
+        level: match result.level {
+            Level::Ok => DiagnosticStatus::OK,
+            Level::Warn => DiagnosticStatus::WARN,
+            Level::Error => DiagnosticStatus::ERROR,
+        },
+        name: result.service.clone(),
+        message: result.message.clone(),
+        hardware_id: String::new(),
+        values: result
+            .latency
+            .map(|latency| KeyValue {
+                key: String::from("latency_ms"),
+                value: format!("{:.3}", latency.as_secs_f64() * 1000.0),
+            })
+            .into_iter()
+            .collect(),
+    }
+}
+
+fn main() {
+    // Fetch args that are not meant for rosrust
+    let args: Vec<_> = rosrust::args();
+
+    let period = match args.get(1).map(|period| period.parse::<f64>()) {
+        None => Some(DEFAULT_PERIOD),
+        Some(Ok(period)) if period.is_finite() && period > 0.0 => Some(period),
+        _ => None,
+    };
+    let period = match period {
+        Some(period) if args.len() <= 2 => period,
+        _ => {
+            println!("usage: health_check [PERIOD_SECS]");
+            println!("period must be a positive number of seconds");
+            return;
+        }
+    };
+
+    rosrust::init("health_check");
+
+    let publisher = rosrust::publish::<DiagnosticArray>("health", 10).unwrap();
+    let mut canaries = canaries();
+    // services seen by previous check, None before the first one
+    let mut known_services: Option<Vec<String>> = None;
+
+    let rate = rosrust::rate(1.0 / period);
+    while rosrust::is_ok() {
+        let services = service_names();
+        let topics = rosrust::topics().map_or(0, |topics| topics.len());
+
+        if let Some(known_services) = &known_services {
+            let (appeared, disappeared) = changes(known_services, &services);
+            for service in appeared {
+                ros_info!("service appeared: {}", service);
+            }
+            for service in disappeared {
+                ros_warn!("service disappeared: {}", service);
+            }
+        }
+
+        let results: Vec<CheckResult> = canaries
+            .iter_mut()
+            .map(|canary| {
+                if !services.iter().any(|name| name == canary.service) {
+                    return CheckResult::unregistered(canary.service);
+                }
+                let start = Instant::now();
+                let outcome = (canary.check)();
+                CheckResult::from_call(canary.service, outcome, start.elapsed(), SLOW_RESPONSE)
+            })
+            .collect();
+
+        let mut msg = DiagnosticArray::default();
+        msg.header.stamp = rosrust::now();
+        msg.status = results.iter().map(diagnostic_status).collect();
+        publisher.send(msg).unwrap();
+
+        println!();
+        for line in summary(&results, services.len(), topics) {
+            println!("{}", line);
+        }
+
+        known_services = Some(services);
+        rate.sleep();
+    }
+}
@@ -0,0 +1,84 @@
+//! Helpers for terminal displays of turtle state.
+use std::collections::VecDeque;
+use std::f64::consts::FRAC_PI_4;
+use std::time::{Duration, Instant};
+
+/// arrow pointing in the direction of given heading
+/// (radians, 0 = east, counter clockwise as turtlesim theta)
+pub fn heading_arrow(theta: f64) -> char {
+    const ARROWS: [char; 8] = ['→', '↗', '↑', '↖', '←', '↙', '↓', '↘'];
+    let sector = ((theta / FRAC_PI_4).round() as i64).rem_euclid(8);
+    ARROWS[sector as usize]
+}
+
+/// measures rate (Hz) of events (e.g. received messages) in sliding time window
+#[derive(Debug, Clone)]
+pub struct RateMeter {
+    window: Duration,
+    events: VecDeque<Instant>,
+}
+
+impl Default for RateMeter {
+    fn default() -> Self {
+        RateMeter::new(Duration::from_secs(1))
+    }
+}
+
+impl RateMeter {
+    pub fn new(window: Duration) -> RateMeter {
+        RateMeter {
+            window,
+            events: VecDeque::new(),
+        }
+    }
+
+    /// records event which happened at given time
+    pub fn tick(&mut self, now: Instant) {
+        self.events.push_back(now);
+        self.forget_old(now);
+    }
+
+    /// events per second within the window preceding given time
+    pub fn rate(&mut self, now: Instant) -> f64 {
+        self.forget_old(now);
+        self.events.len() as f64 / self.window.as_secs_f64()
+    }
+
+    fn forget_old(&mut self, now: Instant) {
+        while let Some(oldest) = self.events.front() {
+            if now.duration_since(*oldest) > self.window {
+                self.events.pop_front();
+            } else {
+                break;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_heading_arrow() {
+        assert_eq!(heading_arrow(0.0), '→');
+        assert_eq!(heading_arrow(90_f64.to_radians()), '↑');
+        assert_eq!(heading_arrow(-90_f64.to_radians()), '↓');
+        assert_eq!(heading_arrow(180_f64.to_radians()), '←');
+        assert_eq!(heading_arrow(-170_f64.to_radians()), '←');
+        assert_eq!(heading_arrow(-135_f64.to_radians()), '↙');
+        assert_eq!(heading_arrow(400_f64.to_radians()), '↗');
+    }
+
+    #[test]
+    fn test_rate_meter() {
+        let t0 = Instant::now();
+        let mut meter = RateMeter::new(Duration::from_secs(2));
+        for i in 0..20 {
+            meter.tick(t0 + Duration::from_millis(i * 100));
+        }
+        assert_eq!(meter.rate(t0 + Duration::from_millis(1900)), 10.0);
+        assert_eq!(meter.rate(t0 + Duration::from_millis(3000)), 5.0);
+        assert_eq!(meter.rate(t0 + Duration::from_secs(10)), 0.0);
+    }
+}
@@ -0,0 +1,138 @@
+//! Latency statistics of service calls (min, mean, percentiles, throughput).
+use std::time::Duration;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct LatencyStats {
+    /// successful calls
+    pub calls: usize,
+    /// failed calls (service error or call failure)
+    pub errors: usize,
+    pub min: Duration,
+    pub mean: Duration,
+    pub p50: Duration,
+    pub p99: Duration,
+    pub max: Duration,
+    /// successful calls per second
+    pub throughput: f64,
+}
+
+/// nearest-rank percentile of sorted samples
+fn percentile(sorted: &[Duration], percent: f64) -> Duration {
+    let rank = (percent / 100.0 * sorted.len() as f64).ceil() as usize;
+    sorted[rank.clamp(1, sorted.len()) - 1]
+}
+
+impl LatencyStats {
+    /// statistics of successful call latencies, elapsed is total time of the run.
+    /// latencies are zero when there is no successful call.
+    pub fn new(mut latencies: Vec<Duration>, errors: usize, elapsed: Duration) -> Self {
+        latencies.sort();
+
+        let calls = latencies.len();
+        if calls == 0 {
+            return LatencyStats {
+                calls,
+                errors,
+                min: Duration::ZERO,
+                mean: Duration::ZERO,
+                p50: Duration::ZERO,
+                p99: Duration::ZERO,
+                max: Duration::ZERO,
+                throughput: 0.0,
+            };
+        }
+
+        LatencyStats {
+            calls,
+            errors,
+            min: latencies[0],
+            mean: latencies.iter().sum::<Duration>() / calls as u32,
+            p50: percentile(&latencies, 50.0),
+            p99: percentile(&latencies, 99.0),
+            max: latencies[calls - 1],
+            throughput: calls as f64 / elapsed.as_secs_f64(),
+        }
+    }
+
+    fn millis(duration: Duration) -> f64 {
+        duration.as_secs_f64() * 1000.0
+    }
+
+    /// header and row of text table, latencies in milliseconds
+    pub fn table(&self, name: &str) -> [String; 2] {
+        [
+            format!(
+                "{:<20} {:>7} {:>6} {:>9} {:>9} {:>9} {:>9} {:>9} {:>10}",
+                "service",
+                "calls",
+                "errors",
+                "min ms",
+                "mean ms",
+                "p50 ms",
+                "p99 ms",
+                "max ms",
+                "calls/s"
+            ),
+            format!(
+                "{:<20} {:>7} {:>6} {:>9.3} {:>9.3} {:>9.3} {:>9.3} {:>9.3} {:>10.1}",
+                name,
+                self.calls,
+                self.errors,
+                Self::millis(self.min),
+                Self::millis(self.mean),
+                Self::millis(self.p50),
+                Self::millis(self.p99),
+                Self::millis(self.max),
+                self.throughput
+            ),
+        ]
+    }
+
+    /// single line JSON object, latencies in milliseconds
+    pub fn json(&self, name: &str) -> String {
+        format!(
+            "{{\"service\":\"{}\",\"calls\":{},\"errors\":{},\"min_ms\":{:.3},\"mean_ms\":{:.3},\"p50_ms\":{:.3},\"p99_ms\":{:.3},\"max_ms\":{:.3},\"calls_per_sec\":{:.1}}}",
+            name,
+            self.calls,
+            self.errors,
+            Self::millis(self.min),
+            Self::millis(self.mean),
+            Self::millis(self.p50),
+            Self::millis(self.p99),
+            Self::millis(self.max),
+            self.throughput
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_stats() {
+        let latencies: Vec<Duration> = (1..=100).rev().map(Duration::from_millis).collect();
+        let stats = LatencyStats::new(latencies, 2, Duration::from_secs(2));
+
+        assert_eq!(stats.calls, 100);
+        assert_eq!(stats.min, Duration::from_millis(1));
+        assert_eq!(stats.mean, Duration::from_micros(50500));
+        assert_eq!(stats.p50, Duration::from_millis(50));
+        assert_eq!(stats.p99, Duration::from_millis(99));
+        assert_eq!(stats.max, Duration::from_millis(100));
+        assert_eq!(stats.throughput, 50.0);
+        assert!(stats.json("add_two_ints").starts_with(
+            "{\"service\":\"add_two_ints\",\"calls\":100,\"errors\":2,\"min_ms\":1.000,"
+        ));
+
+        assert_eq!(
+            percentile(&[Duration::from_millis(7)], 99.0),
+            Duration::from_millis(7)
+        );
+        let failed = LatencyStats::new(vec![], 3, Duration::from_secs(1));
+        assert_eq!((failed.calls, failed.errors), (0, 3));
+        assert!(failed
+            .json("evaluate")
+            .contains("\"errors\":3,\"min_ms\":0.000,"));
+    }
+}
@@ -0,0 +1,19 @@
+//! Code shared by the tutorial nodes.
+
+pub mod audit;
+pub mod battery;
+pub mod discovery;
+pub mod expression;
+pub mod fields;
+pub mod geofence;
+pub mod health;
+pub mod hud;
+pub mod latency;
+pub mod line_editor;
+pub mod map;
+pub mod odometry;
+pub mod pose_log;
+pub mod recording;
+pub mod service_client;
+pub mod shapes;
+pub mod teleop;
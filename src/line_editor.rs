@@ -0,0 +1,232 @@
+//! Editing of a command line in raw terminal mode: cursor movement, history
+//! and completion of the word under cursor.
+
+#[derive(Debug, Default)]
+pub struct LineEditor {
+    line: Vec<char>,
+    /// position in characters
+    cursor: usize,
+    history: Vec<String>,
+    /// index of history entry shown while browsing history
+    history_index: Option<usize>,
+    /// line edited before browsing history started
+    draft: Vec<char>,
+}
+
+impl LineEditor {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn line(&self) -> String {
+        self.line.iter().collect()
+    }
+
+    pub fn cursor(&self) -> usize {
+        self.cursor
+    }
+
+    /// submitted lines, oldest first
+    pub fn history(&self) -> &[String] {
+        &self.history
+    }
+
+    pub fn insert(&mut self, c: char) {
+        self.line.insert(self.cursor, c);
+        self.cursor += 1;
+    }
+
+    pub fn backspace(&mut self) {
+        if self.cursor > 0 {
+            self.cursor -= 1;
+            self.line.remove(self.cursor);
+        }
+    }
+
+    pub fn delete(&mut self) {
+        if self.cursor < self.line.len() {
+            self.line.remove(self.cursor);
+        }
+    }
+
+    pub fn left(&mut self) {
+        self.cursor = self.cursor.saturating_sub(1);
+    }
+
+    pub fn right(&mut self) {
+        self.cursor = (self.cursor + 1).min(self.line.len());
+    }
+
+    pub fn home(&mut self) {
+        self.cursor = 0;
+    }
+
+    pub fn end(&mut self) {
+        self.cursor = self.line.len();
+    }
+
+    pub fn clear(&mut self) {
+        self.line.clear();
+        self.cursor = 0;
+        self.history_index = None;
+    }
+
+    fn show(&mut self, line: Vec<char>) {
+        self.line = line;
+        self.cursor = self.line.len();
+    }
+
+    /// shows older history entry
+    pub fn older(&mut self) {
+        let index = match self.history_index {
+            Some(0) => return,
+            Some(index) => index - 1,
+            None if self.history.is_empty() => return,
+            None => {
+                self.draft = self.line.clone();
+                self.history.len() - 1
+            }
+        };
+        self.history_index = Some(index);
+        self.show(self.history[index].chars().collect());
+    }
+
+    /// shows newer history entry, after the newest one the edited line is back
+    pub fn newer(&mut self) {
+        match self.history_index {
+            Some(index) if index + 1 < self.history.len() => {
+                self.history_index = Some(index + 1);
+                self.show(self.history[index + 1].chars().collect());
+            }
+            Some(_) => {
+                self.history_index = None;
+                let draft = std::mem::take(&mut self.draft);
+                self.show(draft);
+            }
+            None => {}
+        }
+    }
+
+    /// returns the line and starts new one, non empty line is added to history
+    /// (unless it repeats the previous one)
+    pub fn submit(&mut self) -> String {
+        let line = self.line();
+        if !line.trim().is_empty() && self.history.last() != Some(&line) {
+            self.history.push(line.clone());
+        }
+        self.clear();
+        line
+    }
+
+    /// completes the word before cursor from candidates. when more candidates match,
+    /// the word is extended to their common prefix and the matching candidates are
+    /// returned (to be shown to the user).
+    pub fn complete(&mut self, candidates: &[String]) -> Vec<String> {
+        let start = self.line[..self.cursor]
+            .iter()
+            .rposition(|c| c.is_whitespace())
+            .map_or(0, |i| i + 1);
+        let word: String = self.line[start..self.cursor].iter().collect();
+
+        let mut matches: Vec<String> = candidates
+            .iter()
+            .filter(|candidate| candidate.starts_with(&word))
+            .cloned()
+            .collect();
+        matches.sort();
+        matches.dedup();
+
+        let completion: Vec<char> = match matches.as_slice() {
+            [] => return vec![],
+            [single] => single.chars().chain(Some(' ')).collect(),
+            [first, rest @ ..] => {
+                let mut prefix: Vec<char> = first.chars().collect();
+                for other in rest {
+                    let common = prefix
+                        .iter()
+                        .zip(other.chars())
+                        .take_while(|(a, b)| **a == *b)
+                        .count();
+                    prefix.truncate(common);
+                }
+                prefix
+            }
+        };
+
+        self.line
+            .splice(start..self.cursor, completion.iter().copied());
+        self.cursor = start + completion.len();
+
+        if matches.len() > 1 {
+            matches
+        } else {
+            vec![]
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn type_text(editor: &mut LineEditor, text: &str) {
+        text.chars().for_each(|c| editor.insert(c));
+    }
+
+    #[test]
+    fn test_editing_and_history() {
+        let mut editor = LineEditor::new();
+        type_text(&mut editor, "ad 3 4");
+        editor.home();
+        editor.right();
+        editor.insert('d');
+        assert_eq!(editor.line(), "add 3 4");
+        editor.end();
+        editor.backspace();
+        type_text(&mut editor, "5");
+        assert_eq!(editor.submit(), "add 3 5");
+        assert_eq!(editor.line(), "");
+
+        type_text(&mut editor, "area 5 6");
+        editor.submit();
+        editor.submit();
+        type_text(&mut editor, "add 3 5");
+        editor.submit();
+        assert_eq!(editor.history(), ["add 3 5", "area 5 6", "add 3 5"]);
+
+        type_text(&mut editor, "dra");
+        editor.older();
+        editor.older();
+        assert_eq!(editor.line(), "area 5 6");
+        editor.newer();
+        editor.newer();
+        assert_eq!(editor.line(), "dra");
+        assert_eq!(editor.cursor(), 3);
+    }
+
+    #[test]
+    fn test_complete() {
+        let candidates: Vec<String> = ["add", "area", "/add_two_ints", "/add_two_ints_batch"]
+            .iter()
+            .map(|c| c.to_string())
+            .collect();
+
+        let mut editor = LineEditor::new();
+        type_text(&mut editor, "ad");
+        assert!(editor.complete(&candidates).is_empty());
+        assert_eq!(editor.line(), "add ");
+
+        type_text(&mut editor, "/a");
+        assert_eq!(
+            editor.complete(&candidates),
+            vec!["/add_two_ints", "/add_two_ints_batch"]
+        );
+        assert_eq!(editor.line(), "add /add_two_ints");
+
+        type_text(&mut editor, " 3");
+        editor.home();
+        type_text(&mut editor, "x");
+        assert!(editor.complete(&candidates).is_empty());
+        assert_eq!(editor.line(), "xadd /add_two_ints 3");
+    }
+}
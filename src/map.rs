@@ -0,0 +1,164 @@
+//! Top-down character map of the turtlesim arena.
+//!
+//! Arena is drawn as a box of characters with y axis pointing up (as in turtlesim).
+//! Terminal characters are about twice as high as wide, so the map has half as many
+//! rows as columns to keep the arena square.
+use crate::hud::heading_arrow;
+
+/// size of the (square) turtlesim arena
+pub const ARENA_SIZE: f64 = 11.088889;
+
+const TRAIL: char = '·';
+const WAYPOINT: char = '+';
+
+/// scaled character grid covering the arena (size without walls)
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct MapView {
+    pub columns: usize,
+    pub rows: usize,
+}
+
+impl MapView {
+    /// largest view (including walls) fitting into terminal area of given size
+    pub fn fit(width: u16, height: u16) -> MapView {
+        let columns = (width as usize).saturating_sub(2);
+        let rows = (height as usize).saturating_sub(2);
+
+        // character is about twice as high as wide
+        let columns = columns.min(rows * 2);
+        MapView {
+            columns,
+            rows: columns / 2,
+        }
+    }
+
+    /// (column, row) of cell containing given point, None outside the arena
+    pub fn cell(&self, x: f64, y: f64) -> Option<(usize, usize)> {
+        if !(0.0..=ARENA_SIZE).contains(&x) || !(0.0..=ARENA_SIZE).contains(&y) {
+            return None;
+        }
+
+        let column = ((x / ARENA_SIZE * self.columns as f64) as usize).min(self.columns - 1);
+        let row = (((ARENA_SIZE - y) / ARENA_SIZE * self.rows as f64) as usize).min(self.rows - 1);
+        Some((column, row))
+    }
+
+    /// map lines with walls. turtles are given as (x, y, theta) and drawn as heading arrows
+    /// over the trail and waypoints.
+    pub fn render(
+        &self,
+        turtles: &[(f64, f64, f64)],
+        trail: &[(f64, f64)],
+        waypoints: &[(f64, f64)],
+    ) -> Vec<String> {
+        if self.columns == 0 || self.rows == 0 {
+            return vec![];
+        }
+
+        let mut grid = vec![vec![' '; self.columns]; self.rows];
+        let mut put = |x: f64, y: f64, c: char| {
+            if let Some((column, row)) = self.cell(x, y) {
+                grid[row][column] = c;
+            }
+        };
+
+        for &(x, y) in waypoints {
+            put(x, y, WAYPOINT);
+        }
+        for &(x, y) in trail {
+            put(x, y, TRAIL);
+        }
+        for &(x, y, theta) in turtles {
+            put(x, y, heading_arrow(theta));
+        }
+
+        let wall = "─".repeat(self.columns);
+        let mut lines = vec![format!("┌{}┐", wall)];
+        lines.extend(
+            grid.into_iter()
+                .map(|row| format!("│{}│", row.into_iter().collect::<String>())),
+        );
+        lines.push(format!("└{}┘", wall));
+        lines
+    }
+}
+
+/// parses waypoints, one "x y" (or "x,y") per line. empty lines and lines starting with # are skipped
+pub fn parse_waypoints(content: &str) -> Result<Vec<(f64, f64)>, String> {
+    content
+        .lines()
+        .enumerate()
+        .map(|(i, line)| (i, line.trim()))
+        .filter(|(_, line)| !line.is_empty() && !line.starts_with('#'))
+        .map(|(i, line)| {
+            let coordinates: Vec<f64> = line
+                .split(|c: char| c == ',' || c.is_whitespace())
+                .filter(|field| !field.is_empty())
+                .map(str::parse::<f64>)
+                .collect::<Result<_, _>>()
+                .map_err(|e| format!("line {}: {}", i + 1, e))?;
+
+            match coordinates[..] {
+                [x, y] => Ok((x, y)),
+                _ => Err(format!("line {}: expected x y", i + 1)),
+            }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_render() {
+        let view = MapView::fit(12, 40);
+        assert_eq!(
+            view,
+            MapView {
+                columns: 10,
+                rows: 5
+            }
+        );
+        assert_eq!(
+            MapView::fit(80, 7),
+            MapView {
+                columns: 10,
+                rows: 5
+            }
+        );
+
+        assert_eq!(view.cell(0.0, 0.0), Some((0, 4)));
+        assert_eq!(view.cell(ARENA_SIZE, ARENA_SIZE), Some((9, 0)));
+        assert_eq!(view.cell(5.544, 5.544), Some((4, 2)));
+        assert_eq!(view.cell(-0.1, 5.0), None);
+
+        let lines = view.render(
+            &[(5.544, 5.544, 0.0)],
+            &[(1.0, 5.544), (5.0, 5.544)],
+            &[(10.5, 10.5)],
+        );
+        assert_eq!(
+            lines,
+            vec![
+                "┌──────────┐",
+                "│         +│",
+                "│          │",
+                "│·   →     │",
+                "│          │",
+                "│          │",
+                "└──────────┘",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_parse_waypoints() {
+        assert_eq!(
+            parse_waypoints("# path\n1 1\n\n 2.5,3 \n"),
+            Ok(vec![(1.0, 1.0), (2.5, 3.0)])
+        );
+        assert!(parse_waypoints("1").is_err());
+        assert!(parse_waypoints("1 a").is_err());
+    }
+}
@@ -0,0 +1,259 @@
+//! Rolling statistics of received poses, to diagnose odometry quality and timing.
+//!
+//! turtlesim/Pose has no header (and no sequence number), so dropped messages are
+//! estimated from arrival gaps: turtlesim publishes poses with fixed rate, a gap
+//! of n periods means n - 1 messages were lost. The period is the median of recent
+//! intervals. Messages delivered in a burst (near zero intervals, e.g. after network
+//! stall) are not lost, so the burst cancels out the gap next to it.
+use std::collections::VecDeque;
+use std::time::Instant;
+
+/// upper bounds of angular velocity histogram bins (rad/s), last bin is unbounded
+const ANGULAR_BIN_EDGES: [f64; 6] = [-1.5, -0.5, -0.1, 0.1, 0.5, 1.5];
+const ANGULAR_BIN_LABELS: [&str; 7] = [
+    "<-1.5",
+    "-1.5..-0.5",
+    "-0.5..-0.1",
+    "~0",
+    "0.1..0.5",
+    "0.5..1.5",
+    ">1.5",
+];
+
+/// interval longer than this multiple of the expected period is a gap (lost messages)
+const GAP_FACTOR: f64 = 1.5;
+
+/// number of recent intervals the expected publishing period is estimated from
+const PERIOD_WINDOW: usize = 21;
+
+/// intervals shorter than this (seconds) are bursts, not publishing period
+const BURST_INTERVAL: f64 = 0.001;
+
+#[derive(Debug, Clone, Default)]
+pub struct OdometryStats {
+    messages: u64,
+    first: Option<Instant>,
+    last: Option<(Instant, f64, f64)>,
+    distance: f64,
+    max_speed: f64,
+    angular_histogram: [u64; 7],
+    // inter-arrival time (seconds), Welford's online mean and variance
+    intervals: u64,
+    interval_mean: f64,
+    interval_m2: f64,
+    interval_min: f64,
+    interval_max: f64,
+    /// recent intervals (without bursts) for estimate of publishing period
+    recent_intervals: VecDeque<f64>,
+    /// messages estimated as dropped by the last gap (burst may follow the gap)
+    gap_dropped: u64,
+    /// messages received in burst, not yet matched with a gap
+    burst: u64,
+    dropped: u64,
+}
+
+impl OdometryStats {
+    /// updates statistics by pose (position and velocities) received at given time
+    pub fn update(&mut self, x: f64, y: f64, linear_speed: f64, angular_speed: f64, now: Instant) {
+        self.messages += 1;
+        self.first.get_or_insert(now);
+        self.max_speed = self.max_speed.max(linear_speed.abs());
+
+        let bin = ANGULAR_BIN_EDGES
+            .iter()
+            .position(|edge| angular_speed < *edge)
+            .unwrap_or(ANGULAR_BIN_EDGES.len());
+        self.angular_histogram[bin] += 1;
+
+        if let Some((last_time, last_x, last_y)) = self.last {
+            self.distance += (x - last_x).hypot(y - last_y);
+            self.add_interval(now.duration_since(last_time).as_secs_f64());
+        }
+        self.last = Some((now, x, y));
+    }
+
+    fn add_interval(&mut self, interval: f64) {
+        self.intervals += 1;
+        let delta = interval - self.interval_mean;
+        self.interval_mean += delta / self.intervals as f64;
+        self.interval_m2 += delta * (interval - self.interval_mean);
+
+        if self.intervals == 1 {
+            self.interval_min = interval;
+            self.interval_max = interval;
+        } else {
+            self.interval_min = self.interval_min.min(interval);
+            self.interval_max = self.interval_max.max(interval);
+        }
+
+        if interval < BURST_INTERVAL {
+            // message delayed, not lost: cancels out one message of the last gap
+            if self.gap_dropped > 0 {
+                self.gap_dropped -= 1;
+                self.dropped -= 1;
+            } else {
+                self.burst += 1;
+            }
+            return;
+        }
+
+        // burst and gap cancel out only when next to each other
+        self.gap_dropped = 0;
+        let burst = std::mem::take(&mut self.burst);
+        if let Some(period) = self.period().filter(|period| *period > 0.0) {
+            if interval > GAP_FACTOR * period {
+                let missing = ((interval / period).round() as u64).saturating_sub(1);
+                self.gap_dropped = missing.saturating_sub(burst);
+                self.dropped = self.dropped.saturating_add(self.gap_dropped);
+            }
+        }
+
+        // gaps are in the window too, so that the estimate follows changed rate
+        if self.recent_intervals.len() == PERIOD_WINDOW {
+            self.recent_intervals.pop_front();
+        }
+        self.recent_intervals.push_back(interval);
+    }
+
+    /// expected publishing period (median of recent intervals), None until first interval
+    fn period(&self) -> Option<f64> {
+        let mut intervals: Vec<f64> = self.recent_intervals.iter().copied().collect();
+        intervals.sort_by(|a, b| a.total_cmp(b));
+        intervals.get(intervals.len() / 2).copied()
+    }
+
+    pub fn messages(&self) -> u64 {
+        self.messages
+    }
+
+    /// total travelled distance
+    pub fn distance(&self) -> f64 {
+        self.distance
+    }
+
+    /// average speed over the whole time poses are received
+    pub fn average_speed(&self) -> f64 {
+        match (self.first, self.last) {
+            (Some(first), Some((last, _, _))) if last > first => {
+                self.distance / last.duration_since(first).as_secs_f64()
+            }
+            _ => 0.0,
+        }
+    }
+
+    pub fn max_speed(&self) -> f64 {
+        self.max_speed
+    }
+
+    /// standard deviation of message inter-arrival time (seconds)
+    pub fn jitter(&self) -> f64 {
+        if self.intervals < 2 {
+            return 0.0;
+        }
+        (self.interval_m2 / (self.intervals - 1) as f64).sqrt()
+    }
+
+    /// estimated number of lost messages
+    pub fn dropped(&self) -> u64 {
+        self.dropped
+    }
+
+    /// (bin label, count) of angular velocity histogram
+    pub fn angular_histogram(&self) -> impl Iterator<Item = (&'static str, u64)> + '_ {
+        ANGULAR_BIN_LABELS
+            .iter()
+            .copied()
+            .zip(self.angular_histogram.iter().copied())
+    }
+
+    /// statistics as text lines (for display and summary)
+    pub fn summary(&self) -> Vec<String> {
+        let histogram: Vec<String> = self
+            .angular_histogram()
+            .map(|(label, count)| {
+                let percent = if self.messages > 0 {
+                    100.0 * count as f64 / self.messages as f64
+                } else {
+                    0.0
+                };
+                format!("{}: {:.0}%", label, percent)
+            })
+            .collect();
+
+        vec![
+            format!(
+                "distance: {:.2}  avg speed: {:.2}  max speed: {:.2}  messages: {}  dropped (est.): {}",
+                self.distance,
+                self.average_speed(),
+                self.max_speed,
+                self.messages,
+                self.dropped
+            ),
+            format!(
+                "inter-arrival [ms]: mean {:.1}  jitter {:.1}  min {:.1}  max {:.1}",
+                self.interval_mean * 1000.0,
+                self.jitter() * 1000.0,
+                self.interval_min * 1000.0,
+                self.interval_max * 1000.0
+            ),
+            format!("angular velocity [rad/s]: {}", histogram.join("  ")),
+        ]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    #[test]
+    fn test_stats() {
+        let t0 = Instant::now();
+        let mut stats = OdometryStats::default();
+
+        // 1 m/s along x axis, published every 100 ms, messages 5 and 6 lost
+        for i in (0..=10).filter(|i| *i != 5 && *i != 6) {
+            let t = t0 + Duration::from_millis(100 * i);
+            stats.update(i as f64 * 0.1, 0.0, 1.0, if i < 4 { 0.0 } else { 1.0 }, t);
+        }
+
+        assert_eq!(stats.messages(), 9);
+        assert!((stats.distance() - 1.0).abs() < 1e-9);
+        assert!((stats.average_speed() - 1.0).abs() < 1e-9);
+        assert_eq!(stats.max_speed(), 1.0);
+        assert_eq!(stats.dropped(), 2);
+        assert!(stats.jitter() > 0.0);
+
+        let histogram: Vec<_> = stats.angular_histogram().collect();
+        assert_eq!(histogram[3], ("~0", 4));
+        assert_eq!(histogram[5], ("0.5..1.5", 5));
+        assert_eq!(stats.summary().len(), 3);
+    }
+
+    #[test]
+    fn test_dropped_with_bursts() {
+        let t0 = Instant::now();
+        let mut stats = OdometryStats::default();
+
+        // starts with burst of 5 messages, then every 100 ms
+        for _ in 0..5 {
+            stats.update(0.0, 0.0, 0.0, 0.0, t0);
+        }
+        for i in 1..=20 {
+            stats.update(0.0, 0.0, 0.0, 0.0, t0 + Duration::from_millis(100 * i));
+        }
+        assert_eq!(stats.dropped(), 0);
+
+        // 3 messages delayed by 200 ms and delivered at once
+        let t1 = t0 + Duration::from_millis(2300);
+        for _ in 0..3 {
+            stats.update(0.0, 0.0, 0.0, 0.0, t1);
+        }
+        stats.update(0.0, 0.0, 0.0, 0.0, t1 + Duration::from_millis(100));
+        assert_eq!(stats.dropped(), 0);
+
+        // 2 lost
+        stats.update(0.0, 0.0, 0.0, 0.0, t1 + Duration::from_millis(400));
+        assert_eq!(stats.dropped(), 2);
+    }
+}
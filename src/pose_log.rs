@@ -0,0 +1,270 @@
+//! Logging of received poses to CSV or JSON Lines files, rotated by size.
+//!
+//! When the file exceeds maximal size logging continues in the next one:
+//! poses.csv, poses.1.csv, poses.2.csv, ... (each CSV file starts with header).
+//! Existing log is continued, new records are appended to its last file.
+use crate::fields::quoted;
+use std::fs::{File, OpenOptions};
+use std::io::{self, BufWriter, Write};
+use std::path::{Path, PathBuf};
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum LogFormat {
+    Csv,
+    Jsonl,
+}
+
+impl LogFormat {
+    pub fn parse(name: &str) -> Result<LogFormat, String> {
+        match name {
+            "csv" => Ok(LogFormat::Csv),
+            "jsonl" => Ok(LogFormat::Jsonl),
+            _ => Err(format!(
+                "unknown log format {}, expected csv or jsonl",
+                name
+            )),
+        }
+    }
+
+    /// first line of every file
+    fn header(&self) -> Option<&'static str> {
+        match self {
+            LogFormat::Csv => Some("stamp_secs,turtle,x,y,theta,linear_velocity,angular_velocity"),
+            LogFormat::Jsonl => None,
+        }
+    }
+}
+
+/// pose received by a turtle with receive time (seconds since unix epoch)
+#[derive(Debug, Clone, PartialEq)]
+pub struct PoseRecord {
+    pub stamp: f64,
+    pub turtle: String,
+    pub x: f32,
+    pub y: f32,
+    pub theta: f32,
+    pub linear_velocity: f32,
+    pub angular_velocity: f32,
+}
+
+/// JSON has no NaN or infinity
+fn json_number(value: f32) -> String {
+    if value.is_finite() {
+        value.to_string()
+    } else {
+        String::from("null")
+    }
+}
+
+impl PoseRecord {
+    pub fn format(&self, format: LogFormat) -> String {
+        match format {
+            LogFormat::Csv => format!(
+                "{:.6},{},{},{},{},{},{}",
+                self.stamp,
+                self.turtle,
+                self.x,
+                self.y,
+                self.theta,
+                self.linear_velocity,
+                self.angular_velocity
+            ),
+            LogFormat::Jsonl => format!(
+                "{{\"stamp_secs\":{:.6},\"turtle\":{},\"x\":{},\"y\":{},\"theta\":{},\"linear_velocity\":{},\"angular_velocity\":{}}}",
+                self.stamp,
+                quoted(&self.turtle),
+                json_number(self.x),
+                json_number(self.y),
+                json_number(self.theta),
+                json_number(self.linear_velocity),
+                json_number(self.angular_velocity)
+            ),
+        }
+    }
+}
+
+/// path of n-th file of the log, poses.csv -> poses.<n>.csv
+fn segment_path(path: &Path, index: usize) -> PathBuf {
+    if index == 0 {
+        return path.to_path_buf();
+    }
+
+    let stem = path.file_stem().unwrap_or_default().to_string_lossy();
+    let name = match path.extension() {
+        Some(extension) => format!("{}.{}.{}", stem, index, extension.to_string_lossy()),
+        None => format!("{}.{}", stem, index),
+    };
+    path.with_file_name(name)
+}
+
+/// writes records to the log, starting new file when maximal size (bytes) would be exceeded
+pub struct PoseLogger {
+    path: PathBuf,
+    format: LogFormat,
+    max_size: u64,
+    index: usize,
+    written: u64,
+    writer: BufWriter<File>,
+}
+
+/// opens file for appending, returns it with its current size
+fn open_segment(path: &Path) -> io::Result<(BufWriter<File>, u64)> {
+    let file = OpenOptions::new().create(true).append(true).open(path)?;
+    let size = file.metadata()?.len();
+    Ok((BufWriter::new(file), size))
+}
+
+impl PoseLogger {
+    /// opens the log, existing one is continued in its last file
+    pub fn open(path: &Path, format: LogFormat, max_size: u64) -> io::Result<PoseLogger> {
+        let mut index = 0;
+        while segment_path(path, index + 1).exists() {
+            index += 1;
+        }
+
+        let (writer, written) = open_segment(&segment_path(path, index))?;
+        let mut logger = PoseLogger {
+            path: path.to_path_buf(),
+            format,
+            max_size,
+            index,
+            written,
+            writer,
+        };
+        if written == 0 {
+            logger.write_header()?;
+        }
+        Ok(logger)
+    }
+
+    /// file currently written
+    pub fn current_path(&self) -> PathBuf {
+        segment_path(&self.path, self.index)
+    }
+
+    pub fn log(&mut self, record: &PoseRecord) -> io::Result<()> {
+        let line = record.format(self.format);
+        if self.written > 0 && self.written + line.len() as u64 + 1 > self.max_size {
+            self.rotate()?;
+        }
+        self.write_line(&line)
+    }
+
+    pub fn flush(&mut self) -> io::Result<()> {
+        self.writer.flush()?;
+        self.writer.get_ref().sync_all()
+    }
+
+    fn rotate(&mut self) -> io::Result<()> {
+        self.flush()?;
+        self.index += 1;
+        let (writer, written) = open_segment(&self.current_path())?;
+        self.writer = writer;
+        self.written = written;
+        if written == 0 {
+            self.write_header()?;
+        }
+        Ok(())
+    }
+
+    fn write_header(&mut self) -> io::Result<()> {
+        match self.format.header() {
+            Some(header) => self.write_line(header),
+            None => Ok(()),
+        }
+    }
+
+    fn write_line(&mut self, line: &str) -> io::Result<()> {
+        writeln!(self.writer, "{}", line)?;
+        self.written += line.len() as u64 + 1;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    fn record(x: f32) -> PoseRecord {
+        PoseRecord {
+            stamp: 1.5,
+            turtle: String::from("/turtle1"),
+            x,
+            y: 2.0,
+            theta: -0.5,
+            linear_velocity: 1.0,
+            angular_velocity: 0.0,
+        }
+    }
+
+    #[test]
+    fn test_format() {
+        assert_eq!(
+            record(1.25).format(LogFormat::Csv),
+            "1.500000,/turtle1,1.25,2,-0.5,1,0"
+        );
+        assert_eq!(
+            record(1.25).format(LogFormat::Jsonl),
+            "{\"stamp_secs\":1.500000,\"turtle\":\"/turtle1\",\"x\":1.25,\"y\":2,\"theta\":-0.5,\"linear_velocity\":1,\"angular_velocity\":0}"
+        );
+        let mut invalid = record(f32::NAN);
+        invalid.turtle = String::from("/tur\ttle\\1");
+        assert!(invalid
+            .format(LogFormat::Jsonl)
+            .contains("\"turtle\":\"/tur\\ttle\\\\1\",\"x\":null,\"y\":2"));
+        assert!(LogFormat::parse("xml").is_err());
+        assert_eq!(
+            segment_path(Path::new("/tmp/poses.csv"), 2),
+            Path::new("/tmp/poses.2.csv")
+        );
+    }
+
+    #[test]
+    fn test_rotation() {
+        let dir = std::env::temp_dir().join(format!("pose_log_test_{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("poses.csv");
+
+        // header + 2 records fit into one file
+        let line_size = record(1.0).format(LogFormat::Csv).len() as u64 + 1;
+        let header_size = LogFormat::Csv.header().unwrap().len() as u64 + 1;
+        let mut logger =
+            PoseLogger::open(&path, LogFormat::Csv, header_size + 2 * line_size).unwrap();
+        for i in 0..5 {
+            logger.log(&record(i as f32)).unwrap();
+        }
+        logger.flush().unwrap();
+
+        let first = fs::read_to_string(&path).unwrap();
+        assert_eq!(first.lines().count(), 3);
+        assert!(first.starts_with("stamp_secs"));
+        assert_eq!(
+            fs::read_to_string(dir.join("poses.2.csv"))
+                .unwrap()
+                .lines()
+                .count(),
+            2
+        );
+        assert_eq!(logger.current_path(), dir.join("poses.2.csv"));
+        drop(logger);
+
+        // restart continues the last file, history is kept
+        let mut logger =
+            PoseLogger::open(&path, LogFormat::Csv, header_size + 2 * line_size).unwrap();
+        logger.log(&record(5.0)).unwrap();
+        logger.log(&record(6.0)).unwrap();
+        logger.flush().unwrap();
+        assert_eq!(fs::read_to_string(&path).unwrap(), first);
+        assert_eq!(
+            fs::read_to_string(dir.join("poses.2.csv"))
+                .unwrap()
+                .lines()
+                .count(),
+            3
+        );
+        assert_eq!(logger.current_path(), dir.join("poses.3.csv"));
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
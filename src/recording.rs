@@ -0,0 +1,229 @@
+//! Recording and replay of teleop commands (macros).
+//!
+//! Recording is stored as CSV with one published command per line:
+//!
+//! ```text
+//! # time_secs,linear_x,angular_z
+//! 0.000,0.2,0
+//! 0.100,0.4,0
+//! ```
+//!
+//! time is relative to the first recorded command.
+use std::fs;
+use std::path::Path;
+use std::time::{Duration, Instant};
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct RecordedCommand {
+    /// time since the first command of the recording
+    pub offset: Duration,
+    pub linear_speed: f64,
+    pub angular_speed: f64,
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Recording {
+    pub commands: Vec<RecordedCommand>,
+}
+
+impl Recording {
+    pub fn parse(content: &str) -> Result<Recording, String> {
+        let mut commands: Vec<RecordedCommand> = vec![];
+
+        for (i, line) in content.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+
+            let fields: Vec<f64> = line
+                .split(',')
+                .map(|field| field.trim().parse::<f64>())
+                .collect::<Result<_, _>>()
+                .map_err(|e| format!("line {}: {}", i + 1, e))?;
+
+            if fields.len() != 3 {
+                return Err(format!(
+                    "line {}: expected time_secs,linear_x,angular_z",
+                    i + 1
+                ));
+            }
+
+            let offset = Duration::try_from_secs_f64(fields[0])
+                .map_err(|e| format!("line {}: {}", i + 1, e))?;
+            if commands.last().is_some_and(|last| last.offset > offset) {
+                return Err(format!("line {}: time goes backwards", i + 1));
+            }
+
+            commands.push(RecordedCommand {
+                offset,
+                linear_speed: fields[1],
+                angular_speed: fields[2],
+            });
+        }
+
+        Ok(Recording { commands })
+    }
+
+    pub fn serialize(&self) -> String {
+        let mut content = String::from("# time_secs,linear_x,angular_z\n");
+        for command in &self.commands {
+            content.push_str(&format!(
+                "{:.3},{},{}\n",
+                command.offset.as_secs_f64(),
+                command.linear_speed,
+                command.angular_speed
+            ));
+        }
+        content
+    }
+
+    pub fn load(path: &Path) -> Result<Recording, String> {
+        let content = fs::read_to_string(path).map_err(|e| format!("{:?}: {}", path, e))?;
+        Recording::parse(&content)
+    }
+
+    pub fn save(&self, path: &Path) -> Result<(), String> {
+        fs::write(path, self.serialize()).map_err(|e| format!("{:?}: {}", path, e))
+    }
+
+    /// time of the last command
+    pub fn duration(&self) -> Duration {
+        self.commands
+            .last()
+            .map(|command| command.offset)
+            .unwrap_or_default()
+    }
+
+    /// command active at given time since start of the recording
+    /// (the last one sent before). None after the end of the recording.
+    pub fn command_at(&self, time: Duration) -> Option<&RecordedCommand> {
+        if time > self.duration() {
+            return None;
+        }
+        self.commands
+            .iter()
+            .take_while(|command| command.offset <= time)
+            .last()
+    }
+}
+
+/// records commands as they are published
+#[derive(Debug, Default)]
+pub struct Recorder {
+    started: Option<Instant>,
+    recording: Recording,
+}
+
+impl Recorder {
+    pub fn record(&mut self, now: Instant, linear_speed: f64, angular_speed: f64) {
+        let started = *self.started.get_or_insert(now);
+        self.recording.commands.push(RecordedCommand {
+            offset: now.duration_since(started),
+            linear_speed,
+            angular_speed,
+        });
+    }
+
+    pub fn finish(self) -> Recording {
+        self.recording
+    }
+}
+
+/// replay of recording started at given time. speed multiplier scales both time and
+/// velocities, so that turtle drives the same path (just faster or slower)
+#[derive(Debug, Clone)]
+pub struct Playback {
+    pub recording: Recording,
+    pub speed: f64,
+    pub looping: bool,
+    pub started: Instant,
+}
+
+impl Playback {
+    pub fn new(recording: Recording, speed: f64, looping: bool, started: Instant) -> Playback {
+        Playback {
+            recording,
+            speed,
+            looping,
+            started,
+        }
+    }
+
+    /// time in the recording corresponding to given real time
+    fn recording_time(&self, now: Instant) -> Duration {
+        let time = now.duration_since(self.started).mul_f64(self.speed);
+        let duration = self.recording.duration();
+
+        if self.looping && !duration.is_zero() {
+            Duration::from_secs_f64(time.as_secs_f64() % duration.as_secs_f64())
+        } else {
+            time
+        }
+    }
+
+    /// (linear, angular) speed to be sent at given time. None when playback is finished
+    pub fn command(&self, now: Instant) -> Option<(f64, f64)> {
+        self.recording
+            .command_at(self.recording_time(now))
+            .map(|command| {
+                (
+                    command.linear_speed * self.speed,
+                    command.angular_speed * self.speed,
+                )
+            })
+    }
+
+    /// commands with their (speed adjusted) send time relative to the start of one playback pass
+    pub fn schedule(&self) -> impl Iterator<Item = (Duration, f64, f64)> + '_ {
+        self.recording.commands.iter().map(move |command| {
+            (
+                command.offset.div_f64(self.speed),
+                command.linear_speed * self.speed,
+                command.angular_speed * self.speed,
+            )
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sample() -> Recording {
+        let t0 = Instant::now();
+        let mut recorder = Recorder::default();
+        recorder.record(t0, 0.5, 0.0);
+        recorder.record(t0 + Duration::from_millis(100), 1.0, 0.25);
+        recorder.record(t0 + Duration::from_millis(300), 0.0, 0.0);
+        recorder.finish()
+    }
+
+    #[test]
+    fn test_roundtrip() {
+        let recording = sample();
+        assert_eq!(Recording::parse(&recording.serialize()), Ok(recording));
+        assert!(Recording::parse("0.1,1.0").is_err());
+        assert!(Recording::parse("0.2,1,0\n0.1,1,0").is_err());
+        assert!(Recording::parse("abc,1,0").is_err());
+    }
+
+    #[test]
+    fn test_playback() {
+        let t0 = Instant::now();
+        let ms = |ms| t0 + Duration::from_millis(ms);
+
+        let playback = Playback::new(sample(), 1.0, false, t0);
+        assert_eq!(playback.command(ms(50)), Some((0.5, 0.0)));
+        assert_eq!(playback.command(ms(250)), Some((1.0, 0.25)));
+        assert_eq!(playback.command(ms(300)), Some((0.0, 0.0)));
+        assert_eq!(playback.command(ms(400)), None);
+
+        let playback = Playback::new(sample(), 2.0, true, t0);
+        assert_eq!(playback.command(ms(60)), Some((2.0, 0.5)));
+        // second pass
+        assert_eq!(playback.command(ms(170)), Some((1.0, 0.0)));
+        let schedule: Vec<_> = playback.schedule().collect();
+        assert_eq!(schedule[2], (Duration::from_millis(150), 0.0, 0.0));
+    }
+}
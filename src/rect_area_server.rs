@@ -1,17 +1,62 @@
+use ros_tutorial::audit::Audit;
+use std::path::Path;
+
 // rosmsg_include works properly only with linux line end i.e. LF
 // it does now work properly with windows CR LF!!
 mod srv {
-    rosrust::rosmsg_include!(rosservices / RectArea);
+    rosrust::rosmsg_include!(rosservices / RectArea, rosservices / ServiceStats);
 }
 
 fn main() {
     rosrust::init("rect_area_server");
 
+    // Every request is recorded to audit log, given by argument
+    let log = rosrust::args()
+        .get(1)
+        .cloned()
+        .unwrap_or_else(|| String::from("rect_area_service.audit.jsonl"));
+    let audit = match Audit::new(Some(Path::new(&log))) {
+        Ok(audit) => audit,
+        Err(e) => {
+            rosrust::ros_err!("unable to open audit log {}: {}", log, e);
+            return;
+        }
+    };
+    let _stats_raii = audit
+        .advertise_stats::<srv::rosservices::ServiceStats, _>(|stats| {
+            srv::rosservices::ServiceStatsRes {
+                services: stats.services,
+                requests: stats.requests,
+                errors: stats.errors,
+                mean_ms: stats.mean_ms,
+                max_ms: stats.max_ms,
+            }
+        })
+        .unwrap();
+
     // Create service
     // The service is stopped when the returned object is destroyed
-    let _service_raii =
-        rosrust::service::<srv::rosservices::RectArea, _>("rect_area_service", move |req| {
-            let area = req.width * req.length;
+    let _service_raii = audit
+        .service::<srv::rosservices::RectArea, _>("rect_area_service", move |req| {
+            // Err is sent back to the client as service error
+            let area = if req.width < 0 || req.length < 0 {
+                Err(format!(
+                    "dimensions must not be negative, got {} * {}",
+                    req.width, req.length
+                ))
+            } else {
+                req.width
+                    .checked_mul(req.length)
+                    .ok_or_else(|| format!("{} * {} overflows int64", req.width, req.length))
+            };
+
+            let area = match area {
+                Ok(area) => area,
+                Err(error) => {
+                    rosrust::ros_warn!("{}", error);
+                    return Err(error);
+                }
+            };
 
             rosrust::ros_info!("{} * {} = {}", req.width, req.length, area);
 
@@ -19,7 +64,7 @@ fn main() {
         })
         .unwrap();
 
-    rosrust::ros_info!("rect_area_service running!");
+    rosrust::ros_info!("rect_area_service running, audit log {}", log);
 
     rosrust::spin();
 }
@@ -0,0 +1,178 @@
+use ros_tutorial::fields::{
+    parse_assignments, parse_yaml, to_json, to_yaml, Fields, FromValue, ToValue,
+};
+use ros_tutorial::service_client::{CallPolicy, ServiceClient};
+use std::fs;
+use std::io::{self, Read};
+
+// rosmsg_include works properly only with linux line end i.e. LF
+// it does now work properly with windows CR LF!!
+mod srv {
+    rosrust::rosmsg_include!(
+        rosservices / AddTwoInts,
+        rosservices / AddTwoIntsBatch,
+        rosservices / RectArea,
+        rosservices / Evaluate,
+        rosservices / ShapeMetrics,
+        rosservices / ServiceStats
+    );
+}
+
+use srv::rosservices::*;
+
+/// Generates table of known services and function calling service of given type.
+/// To make a new service callable, add its default name, type and fields of request
+/// and response (same as in .srv file).
+macro_rules! services {
+    ($($name:literal => $srv:ident($req:ident { $($in:ident),* } -> $res:ident { $($out:ident),* })),* $(,)?) => {
+        /// (default service name, service type, request fields)
+        const SERVICES: &[(&str, &str, &[&str])] = &[
+            $(($name, stringify!($srv), &[$(stringify!($in)),*])),*
+        ];
+
+        // request of some services (e.g. stats) has no fields
+        #[allow(unused_mut, unused_variables)]
+        fn call(service: &str, srv_type: &str, fields: &Fields) -> Result<Fields, String> {
+            // if chain, not match: the same type may be listed under more names
+            $(if srv_type == stringify!($srv) {
+                // missing fields keep default value (as with rosservice call)
+                let mut req = $req::default();
+                for (key, value) in fields {
+                    match key.as_str() {
+                        $(stringify!($in) => {
+                            req.$in = FromValue::from_value(value)
+                                .map_err(|e| format!("{}: {}", key, e))?;
+                        })*
+                        _ => {
+                            return Err(format!(
+                                "{} has no field {}, fields: {}",
+                                srv_type,
+                                key,
+                                <[&str]>::join(&[$(stringify!($in)),*], ", ")
+                            ))
+                        }
+                    }
+                }
+
+                let res: $res = ServiceClient::<$srv>::new(service, CallPolicy::default())
+                    .call(&req)
+                    .map_err(|e| e.to_string())?;
+                return Ok(vec![$((stringify!($out).to_string(), res.$out.to_value())),*]);
+            })*
+            Err(format!("unknown service type {}", srv_type))
+        }
+    };
+}
+
+services! {
+    "add_two_ints" => AddTwoInts(AddTwoIntsReq { a, b } -> AddTwoIntsRes { sum }),
+    "add_two_ints_batch" => AddTwoIntsBatch(AddTwoIntsBatchReq { a, b } -> AddTwoIntsBatchRes { sums }),
+    "rect_area_service" => RectArea(RectAreaReq { width, length } -> RectAreaRes { area }),
+    "evaluate" => Evaluate(EvaluateReq { expression, names, values } -> EvaluateRes { result }),
+    "shape_metrics" => ShapeMetrics(ShapeMetricsReq { kind, width, length, radius, sides, side_length, x, y }
+        -> ShapeMetricsRes { area, perimeter, centroid_x, centroid_y }),
+    "add_two_ints_server/stats" => ServiceStats(ServiceStatsReq {}
+        -> ServiceStatsRes { services, requests, errors, mean_ms, max_ms }),
+    "rect_area_server/stats" => ServiceStats(ServiceStatsReq {}
+        -> ServiceStatsRes { services, requests, errors, mean_ms, max_ms }),
+}
+
+fn usage() {
+    println!("usage: ros_call [--json] [--type TYPE] SERVICE [KEY=VALUE ...]");
+    println!("       ros_call [--json] [--type TYPE] SERVICE --yaml FILE   (- for stdin)");
+    println!("       ros_call --list");
+    println!("example: ros_call shape_metrics kind=polygon x=[0,2,2] y=[0,0,2]");
+    println!("type is needed only for services running under other than default name");
+}
+
+fn main() {
+    // Fetch args that are not meant for rosrust
+    let mut args: Vec<_> = rosrust::args().into_iter().skip(1).collect();
+
+    if args.first().map(String::as_str) == Some("--list") {
+        for (name, srv_type, fields) in SERVICES {
+            println!("{:<26} {:<16} {}", name, srv_type, fields.join(" "));
+        }
+        return;
+    }
+
+    let json = match args.iter().position(|a| a == "--json") {
+        Some(i) => {
+            args.remove(i);
+            true
+        }
+        None => false,
+    };
+
+    let srv_type = match args.iter().position(|a| a == "--type") {
+        Some(i) if i + 1 < args.len() => {
+            let srv_type = args.remove(i + 1);
+            args.remove(i);
+            Some(srv_type)
+        }
+        Some(_) => {
+            usage();
+            return;
+        }
+        None => None,
+    };
+
+    if args.is_empty() {
+        usage();
+        return;
+    }
+    let service = args.remove(0);
+
+    let srv_type = match srv_type.or_else(|| {
+        SERVICES
+            .iter()
+            .find(|(name, _, _)| *name == service.trim_start_matches('/'))
+            .map(|(_, srv_type, _)| srv_type.to_string())
+    }) {
+        Some(srv_type) => srv_type,
+        None => {
+            println!(
+                "unknown service {}, use --type to give its type (see --list)",
+                service
+            );
+            return;
+        }
+    };
+
+    let fields = match args.first().map(String::as_str) {
+        Some("--yaml") => {
+            let content = match args.get(1).map(String::as_str) {
+                Some("-") => {
+                    let mut content = String::new();
+                    io::stdin()
+                        .read_to_string(&mut content)
+                        .map(|_| content)
+                        .map_err(|e| e.to_string())
+                }
+                Some(path) => fs::read_to_string(path).map_err(|e| format!("{}: {}", path, e)),
+                None => Err(String::from("--yaml needs FILE or -")),
+            };
+            content.and_then(|content| parse_yaml(&content))
+        }
+        _ => parse_assignments(&args),
+    };
+
+    let fields = match fields {
+        Ok(fields) => fields,
+        Err(e) => {
+            println!("invalid request: {}", e);
+            return;
+        }
+    };
+
+    rosrust::init("ros_call");
+
+    match call(&service, &srv_type, &fields) {
+        Ok(response) if json => println!("{}", to_json(&response)),
+        Ok(response) => print!("{}", to_yaml(&response)),
+        Err(e) => {
+            eprintln!("ERROR: {}", e);
+            std::process::exit(1);
+        }
+    }
+}
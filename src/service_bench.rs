@@ -0,0 +1,229 @@
+use ros_tutorial::latency::LatencyStats;
+use rosrust::ServicePair;
+use rosrust::ServiceResult;
+use std::sync::mpsc;
+use std::thread;
+use std::time::{Duration, Instant};
+
+// rosmsg_include works properly only with linux line end i.e. LF
+// it does now work properly with windows CR LF!!
+mod srv {
+    rosrust::rosmsg_include!(
+        rosservices / AddTwoInts,
+        rosservices / AddTwoIntsBatch,
+        rosservices / RectArea,
+        rosservices / Evaluate,
+        rosservices / ShapeMetrics
+    );
+}
+
+use srv::rosservices::*;
+
+/// how long to wait for the service to appear
+const SERVICE_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// how the calls are made
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Mode {
+    /// one synchronous call after another
+    Sync,
+    /// given number of req_async calls kept in flight
+    Async(usize),
+}
+
+/// latencies of successful calls and number of failed ones
+#[derive(Debug, Default)]
+struct Samples {
+    latencies: Vec<Duration>,
+    errors: usize,
+}
+
+impl Samples {
+    fn add<R>(
+        &mut self,
+        service: &str,
+        result: rosrust::error::Result<ServiceResult<R>>,
+        latency: Duration,
+    ) {
+        let error = match result {
+            Ok(Ok(_)) => {
+                self.latencies.push(latency);
+                return;
+            }
+            Ok(Err(e)) => format!("service error: {}", e),
+            Err(e) => format!("call failed: {}", e),
+        };
+        // failed calls are counted in the report, only the first one is logged
+        if self.errors == 0 {
+            rosrust::ros_warn!("{} {}", service, error);
+        }
+        self.errors += 1;
+    }
+}
+
+/// calls the service count times with given request
+fn bench<T: ServicePair>(
+    service: &str,
+    req: T::Request,
+    count: usize,
+    mode: Mode,
+) -> Result<LatencyStats, String> {
+    rosrust::wait_for_service(service, Some(SERVICE_TIMEOUT))
+        .map_err(|e| format!("{} service not available: {}", service, e))?;
+    let client = rosrust::client::<T>(service).map_err(|e| e.to_string())?;
+
+    let mut samples = Samples::default();
+    let t0 = Instant::now();
+    match mode {
+        Mode::Sync => {
+            for _ in 0..count {
+                let started = Instant::now();
+                let result = client.req(&req);
+                samples.add(service, result, started.elapsed());
+            }
+        }
+        Mode::Async(concurrency) => {
+            // each response is read by its own thread, so the latency is measured when
+            // the call completes, not when the older calls in flight do
+            let (sender, receiver) = mpsc::channel();
+            let mut in_flight = 0;
+            for _ in 0..count {
+                if in_flight == concurrency {
+                    let (result, latency) = receiver.recv().unwrap();
+                    samples.add(service, result, latency);
+                    in_flight -= 1;
+                }
+
+                let started = Instant::now();
+                let response = client.req_async(req.clone());
+                let sender = sender.clone();
+                thread::spawn(move || {
+                    let result = response.read();
+                    let _ = sender.send((result, started.elapsed()));
+                });
+                in_flight += 1;
+            }
+
+            for _ in 0..in_flight {
+                let (result, latency) = receiver.recv().unwrap();
+                samples.add(service, result, latency);
+            }
+        }
+    }
+
+    Ok(LatencyStats::new(
+        samples.latencies,
+        samples.errors,
+        t0.elapsed(),
+    ))
+}
+
+fn usage(services: &[&str]) {
+    println!("usage: service_bench SERVICE [COUNT] [--async CONCURRENCY] [table|json]");
+    println!("services: {}", services.join(", "));
+    println!("calls are synchronous unless --async is given");
+    println!("example: service_bench add_two_ints 1000 --async 8 json");
+}
+
+fn main() {
+    // Fetch args that are not meant for rosrust
+    let mut args: Vec<_> = rosrust::args();
+
+    let services = [
+        "add_two_ints",
+        "add_two_ints_batch",
+        "rect_area_service",
+        "evaluate",
+        "shape_metrics",
+    ];
+
+    let mut mode = Mode::Sync;
+    if let Some(i) = args.iter().position(|a| a == "--async") {
+        match args.get(i + 1).and_then(|n| n.parse::<usize>().ok()) {
+            Some(concurrency) if concurrency > 0 => mode = Mode::Async(concurrency),
+            _ => {
+                println!("--async needs positive number of calls in flight");
+                return usage(&services);
+            }
+        }
+        args.drain(i..i + 2);
+    }
+
+    if args.len() < 2 || args.len() > 4 || !services.contains(&args[1].as_str()) {
+        return usage(&services);
+    }
+
+    let service = args[1].as_str();
+    let count = match args.get(2).map(|a| a.parse::<usize>()) {
+        None => 1000,
+        Some(Ok(count)) if count > 0 => count,
+        _ => {
+            println!("COUNT must be positive integer");
+            return usage(&services);
+        }
+    };
+    let json = match args.get(3).map(String::as_str) {
+        None | Some("table") => false,
+        Some("json") => true,
+        Some(other) => {
+            println!("unknown output format {}, expected table or json", other);
+            return;
+        }
+    };
+
+    rosrust::init("service_bench");
+
+    let stats = match service {
+        "add_two_ints" => bench::<AddTwoInts>(service, AddTwoIntsReq { a: 1, b: 2 }, count, mode),
+        "add_two_ints_batch" => bench::<AddTwoIntsBatch>(
+            service,
+            AddTwoIntsBatchReq {
+                a: (0..100).collect(),
+                b: (0..100).collect(),
+            },
+            count,
+            mode,
+        ),
+        "rect_area_service" => bench::<RectArea>(
+            service,
+            RectAreaReq {
+                width: 3,
+                length: 4,
+            },
+            count,
+            mode,
+        ),
+        "evaluate" => bench::<Evaluate>(
+            service,
+            EvaluateReq {
+                expression: String::from("sqrt(x^2 + y^2)"),
+                names: vec![String::from("x"), String::from("y")],
+                values: vec![3.0, 4.0],
+            },
+            count,
+            mode,
+        ),
+        "shape_metrics" => bench::<ShapeMetrics>(
+            service,
+            ShapeMetricsReq {
+                kind: String::from("polygon"),
+                x: vec![0.0, 2.0, 2.0, 0.0],
+                y: vec![0.0, 0.0, 2.0, 2.0],
+                ..Default::default()
+            },
+            count,
+            mode,
+        ),
+        _ => unreachable!(),
+    };
+
+    match stats {
+        Ok(stats) if json => println!("{}", stats.json(service)),
+        Ok(stats) => {
+            for line in stats.table(service) {
+                println!("{}", line);
+            }
+        }
+        Err(e) => rosrust::ros_err!("{}", e),
+    }
+}
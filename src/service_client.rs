@@ -0,0 +1,213 @@
+//! Service client with per-call timeout, retries with exponential backoff and
+//! reconnection (client is re-created after connection problems, e.g. when the
+//! server restarts).
+//!
+//! Only "service unavailable" and "timeout" failures are retried. Error returned
+//! by the server itself would most likely be returned again, so it is not.
+//!
+//! Timed out request is not cancelled (ROS services can't be), the server may
+//! still handle it. So with retries a request may be executed more than once,
+//! services called with retries should be idempotent.
+use rosrust::{ros_warn, Client, ServicePair};
+use std::fmt;
+use std::sync::mpsc;
+use std::thread;
+use std::time::Duration;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum CallError {
+    /// service is not registered or connection to it failed
+    Unavailable(String),
+    /// no response within the call timeout
+    Timeout(Duration),
+    /// error returned by the service
+    Service(String),
+}
+
+impl fmt::Display for CallError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            CallError::Unavailable(e) => write!(f, "service unavailable: {}", e),
+            CallError::Timeout(timeout) => write!(f, "no response within {:?}", timeout),
+            CallError::Service(e) => write!(f, "service returned error: {}", e),
+        }
+    }
+}
+
+impl std::error::Error for CallError {}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct CallPolicy {
+    /// how long to wait for the service to appear when (re)connecting
+    pub connect_timeout: Duration,
+    /// how long to wait for the response of one call
+    pub call_timeout: Duration,
+    /// number of retries after the first failed attempt
+    pub retries: u32,
+    /// delay before the first retry, doubled with each next one
+    pub initial_backoff: Duration,
+    pub max_backoff: Duration,
+}
+
+impl Default for CallPolicy {
+    fn default() -> Self {
+        CallPolicy {
+            connect_timeout: Duration::from_secs(5),
+            call_timeout: Duration::from_secs(2),
+            retries: 3,
+            initial_backoff: Duration::from_millis(200),
+            max_backoff: Duration::from_secs(5),
+        }
+    }
+}
+
+impl CallPolicy {
+    /// delay before given retry (counted from 1)
+    pub fn backoff(&self, retry: u32) -> Duration {
+        let factor = 2u32.saturating_pow(retry.saturating_sub(1));
+        self.initial_backoff
+            .saturating_mul(factor)
+            .min(self.max_backoff)
+    }
+}
+
+type Reply<T> = rosrust::error::Result<rosrust::ServiceResult<<T as ServicePair>::Response>>;
+
+/// thread making synchronous calls, so that waiting for the response can time out.
+/// after timeout the worker is dropped, its thread ends when the pending call
+/// returns (or the connection fails), so at most one thread per timed out call
+/// is left running.
+struct Worker<T: ServicePair> {
+    requests: mpsc::Sender<T::Request>,
+    responses: mpsc::Receiver<Reply<T>>,
+}
+
+impl<T: ServicePair> Worker<T> {
+    fn spawn(client: Client<T>) -> Self {
+        let (requests, request_receiver) = mpsc::channel::<T::Request>();
+        let (response_sender, responses) = mpsc::channel();
+
+        thread::spawn(move || {
+            for req in request_receiver {
+                if response_sender.send(client.req(&req)).is_err() {
+                    break;
+                }
+            }
+        });
+
+        Worker {
+            requests,
+            responses,
+        }
+    }
+}
+
+pub struct ServiceClient<T: ServicePair> {
+    service: String,
+    pub policy: CallPolicy,
+    /// None until connected or after connection problem
+    client: Option<Client<T>>,
+    worker: Option<Worker<T>>,
+}
+
+impl<T: ServicePair> ServiceClient<T> {
+    /// client of given service, connection is established by the first call
+    pub fn new(service: &str, policy: CallPolicy) -> Self {
+        ServiceClient {
+            service: service.to_string(),
+            policy,
+            client: None,
+            worker: None,
+        }
+    }
+
+    /// underlying rosrust client, connects when not connected yet
+    pub fn client(&mut self) -> Result<&Client<T>, CallError> {
+        if self.client.is_none() {
+            rosrust::wait_for_service(&self.service, Some(self.policy.connect_timeout))
+                .map_err(|e| CallError::Unavailable(format!("{}: {}", self.service, e)))?;
+            let client = rosrust::client::<T>(&self.service)
+                .map_err(|e| CallError::Unavailable(format!("{}: {}", self.service, e)))?;
+            self.client = Some(client);
+        }
+
+        Ok(self.client.as_ref().unwrap())
+    }
+
+    /// one attempt without retries
+    fn call_once(&mut self, req: &T::Request) -> Result<T::Response, CallError> {
+        let timeout = self.policy.call_timeout;
+        if self.worker.is_none() {
+            let client = self.client()?.clone();
+            self.worker = Some(Worker::spawn(client));
+        }
+        let worker = self.worker.as_ref().unwrap();
+
+        let ended = || CallError::Unavailable(format!("{}: call thread ended", self.service));
+        worker.requests.send(req.clone()).map_err(|_| ended())?;
+        match worker.responses.recv_timeout(timeout) {
+            Ok(Ok(Ok(res))) => Ok(res),
+            Ok(Ok(Err(e))) => Err(CallError::Service(e)),
+            Ok(Err(e)) => Err(CallError::Unavailable(format!("{}: {}", self.service, e))),
+            Err(mpsc::RecvTimeoutError::Timeout) => Err(CallError::Timeout(timeout)),
+            Err(mpsc::RecvTimeoutError::Disconnected) => Err(ended()),
+        }
+    }
+
+    /// calls the service, failed attempts are retried according to the policy
+    pub fn call(&mut self, req: &T::Request) -> Result<T::Response, CallError> {
+        let mut retry = 0;
+        loop {
+            match self.call_once(req) {
+                Ok(res) => return Ok(res),
+                Err(CallError::Service(e)) => return Err(CallError::Service(e)),
+                Err(e) => {
+                    // server may have restarted, connect again by next call. worker
+                    // possibly waiting for timed out response is abandoned.
+                    self.client = None;
+                    self.worker = None;
+                    if retry >= self.policy.retries || !rosrust::is_ok() {
+                        return Err(e);
+                    }
+
+                    retry += 1;
+                    let backoff = self.policy.backoff(retry);
+                    ros_warn!(
+                        "{} call failed ({}), retry {}/{} in {:?}",
+                        self.service,
+                        e,
+                        retry,
+                        self.policy.retries,
+                        backoff
+                    );
+                    thread::sleep(backoff);
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_backoff() {
+        let policy = CallPolicy {
+            initial_backoff: Duration::from_millis(100),
+            max_backoff: Duration::from_millis(500),
+            ..Default::default()
+        };
+
+        assert_eq!(policy.backoff(1), Duration::from_millis(100));
+        assert_eq!(policy.backoff(2), Duration::from_millis(200));
+        assert_eq!(policy.backoff(3), Duration::from_millis(400));
+        assert_eq!(policy.backoff(4), Duration::from_millis(500));
+        assert_eq!(policy.backoff(100), Duration::from_millis(500));
+
+        assert_eq!(
+            CallError::Timeout(Duration::from_secs(2)).to_string(),
+            "no response within 2s"
+        );
+    }
+}
@@ -0,0 +1,292 @@
+use crossterm::cursor;
+use crossterm::event::{poll, read, Event, KeyCode, KeyEvent, KeyModifiers};
+use crossterm::execute;
+use crossterm::style::Print;
+use crossterm::terminal::{disable_raw_mode, enable_raw_mode, Clear, ClearType};
+use ros_tutorial::discovery::service_names;
+use ros_tutorial::line_editor::LineEditor;
+use ros_tutorial::service_client::{CallPolicy, ServiceClient};
+use std::collections::HashMap;
+use std::io::{stdout, Stdout};
+use std::time::{Duration, Instant};
+
+// rosmsg_include works properly only with linux line end i.e. LF
+// it does now work properly with windows CR LF!!
+mod srv {
+    rosrust::rosmsg_include!(rosservices / AddTwoInts, rosservices / RectArea);
+}
+
+use srv::rosservices::{AddTwoInts, AddTwoIntsReq, RectArea, RectAreaReq};
+
+const PROMPT: &str = "> ";
+
+const COMMANDS: &[&str] = &["add", "area", "services", "history", "help", "quit"];
+
+const HELP: &[&str] = &[
+    "add [SERVICE] X Y          call add_two_ints (or other AddTwoInts service)",
+    "area [SERVICE] W L         call rect_area_service (or other RectArea service)",
+    "services                   list services registered at ROS master",
+    "history                    list entered commands",
+    "help                       show this help",
+    "quit                       exit (also Ctrl-D, Ctrl-C on empty line)",
+    "Tab completes commands and service names, Up/Down browse history",
+];
+
+/// how often services are discovered (when completing)
+const DISCOVERY_PERIOD: Duration = Duration::from_secs(2);
+
+/// shell must stay responsive when a service is down, so calls fail fast
+/// and are not retried (the user can simply repeat the command)
+fn call_policy() -> CallPolicy {
+    CallPolicy {
+        connect_timeout: Duration::from_secs(1),
+        call_timeout: Duration::from_secs(2),
+        retries: 0,
+        ..Default::default()
+    }
+}
+
+/// clients by service name, each service is connected once and reconnected
+/// only after a failure
+#[derive(Default)]
+struct Clients {
+    add: HashMap<String, ServiceClient<AddTwoInts>>,
+    area: HashMap<String, ServiceClient<RectArea>>,
+}
+
+/// services known from ROS master, refreshed when older than DISCOVERY_PERIOD
+struct Services {
+    names: Vec<String>,
+    discovered: Option<Instant>,
+}
+
+impl Services {
+    fn names(&mut self) -> &[String] {
+        if !matches!(self.discovered, Some(t) if t.elapsed() <= DISCOVERY_PERIOD) {
+            self.names = service_names();
+            self.discovered = Some(Instant::now());
+        }
+        &self.names
+    }
+}
+
+/// parses "[SERVICE] X Y" arguments
+fn parse_args(args: &[&str], default_service: &str) -> Result<(String, i64, i64), String> {
+    let (service, numbers) = match args {
+        [x, y] => (default_service, [x, y]),
+        [service, x, y] => (*service, [x, y]),
+        _ => return Err(String::from("expected [SERVICE] X Y")),
+    };
+    let parse = |n: &str| {
+        n.parse::<i64>()
+            .map_err(|_| format!("{} is not an integer", n))
+    };
+
+    Ok((service.to_string(), parse(numbers[0])?, parse(numbers[1])?))
+}
+
+/// executes the command, returns lines to print
+fn run_command(
+    line: &str,
+    clients: &mut Clients,
+    services: &mut Services,
+    editor: &LineEditor,
+) -> Result<Vec<String>, String> {
+    let words: Vec<&str> = line.split_whitespace().collect();
+    let (command, args) = match words.split_first() {
+        Some((command, args)) => (*command, args),
+        None => return Ok(vec![]),
+    };
+
+    match command {
+        "add" => {
+            let (service, a, b) = parse_args(args, "add_two_ints")?;
+            let res = clients
+                .add
+                .entry(service.clone())
+                .or_insert_with(|| ServiceClient::new(&service, call_policy()))
+                .call(&AddTwoIntsReq { a, b })
+                .map_err(|e| format!("{}: {}", service, e))?;
+            Ok(vec![format!("{} + {} = {}", a, b, res.sum)])
+        }
+        "area" => {
+            let (service, width, length) = parse_args(args, "rect_area_service")?;
+            let res = clients
+                .area
+                .entry(service.clone())
+                .or_insert_with(|| ServiceClient::new(&service, call_policy()))
+                .call(&RectAreaReq { width, length })
+                .map_err(|e| format!("{}: {}", service, e))?;
+            Ok(vec![format!("{} * {} = {}", width, length, res.area)])
+        }
+        "services" => {
+            services.discovered = None;
+            Ok(services.names().to_vec())
+        }
+        "history" => Ok(editor
+            .history()
+            .iter()
+            .enumerate()
+            .map(|(i, line)| format!("{:>4}  {}", i + 1, line))
+            .collect()),
+        "help" => Ok(HELP.iter().map(|line| line.to_string()).collect()),
+        _ => Err(format!("unknown command {}, type help", command)),
+    }
+}
+
+/// prints lines below the prompt (raw mode needs \r)
+fn print_lines(stdout: &mut Stdout, lines: &[String]) {
+    for line in lines {
+        execute!(stdout, Print(line), Print("\r\n")).unwrap();
+    }
+}
+
+fn render(stdout: &mut Stdout, editor: &LineEditor) {
+    let line = editor.line();
+    execute!(
+        stdout,
+        Print("\r"),
+        Clear(ClearType::CurrentLine),
+        Print(PROMPT),
+        Print(&line)
+    )
+    .unwrap();
+
+    let after_cursor = line.chars().count() - editor.cursor();
+    if after_cursor > 0 {
+        execute!(stdout, cursor::MoveLeft(after_cursor as u16)).unwrap();
+    }
+}
+
+fn main() {
+    rosrust::init("service_repl");
+
+    let mut stdout = stdout();
+    let mut editor = LineEditor::new();
+    let mut clients = Clients::default();
+    let mut services = Services {
+        names: vec![],
+        discovered: None,
+    };
+
+    enable_raw_mode().unwrap();
+    print_lines(
+        &mut stdout,
+        &[String::from(
+            "tutorial services shell, type help for commands",
+        )],
+    );
+    render(&mut stdout, &editor);
+
+    while rosrust::is_ok() {
+        if !poll(Duration::from_millis(100)).unwrap() {
+            continue;
+        }
+
+        let event = match read().unwrap() {
+            Event::Key(event) => event,
+            _ => continue,
+        };
+
+        match event {
+            KeyEvent {
+                code: KeyCode::Char('c') | KeyCode::Char('d'),
+                modifiers: KeyModifiers::CONTROL,
+            } if editor.line().is_empty() => {
+                print_lines(&mut stdout, &[String::new()]);
+                break;
+            }
+            KeyEvent {
+                code: KeyCode::Char('c'),
+                modifiers: KeyModifiers::CONTROL,
+            } => {
+                // like shells, Ctrl-C discards the line
+                print_lines(&mut stdout, &[String::from("^C")]);
+                editor.clear();
+            }
+            KeyEvent {
+                code: KeyCode::Char(c),
+                modifiers: KeyModifiers::NONE | KeyModifiers::SHIFT,
+            } => editor.insert(c),
+            KeyEvent {
+                code: KeyCode::Enter,
+                ..
+            } => {
+                print_lines(&mut stdout, &[String::new()]);
+                let line = editor.submit();
+                if matches!(line.trim(), "quit" | "exit") {
+                    break;
+                }
+                match run_command(&line, &mut clients, &mut services, &editor) {
+                    Ok(lines) => print_lines(&mut stdout, &lines),
+                    Err(e) => print_lines(&mut stdout, &[format!("error: {}", e)]),
+                }
+            }
+            KeyEvent {
+                code: KeyCode::Tab, ..
+            } => {
+                // first word is command, other words are completed to service names,
+                // both absolute and relative (without the leading /)
+                let first_word = !editor
+                    .line()
+                    .chars()
+                    .take(editor.cursor())
+                    .any(char::is_whitespace);
+                let candidates: Vec<String> = if first_word {
+                    COMMANDS.iter().map(|c| c.to_string()).collect()
+                } else {
+                    let names = services.names();
+                    names
+                        .iter()
+                        .cloned()
+                        .chain(
+                            names
+                                .iter()
+                                .filter_map(|name| name.strip_prefix('/').map(String::from)),
+                        )
+                        .collect()
+                };
+
+                let matches = editor.complete(&candidates);
+                if !matches.is_empty() {
+                    print_lines(&mut stdout, &[String::new(), matches.join("  ")]);
+                }
+            }
+            KeyEvent {
+                code: KeyCode::Backspace,
+                ..
+            } => editor.backspace(),
+            KeyEvent {
+                code: KeyCode::Delete,
+                ..
+            } => editor.delete(),
+            KeyEvent {
+                code: KeyCode::Left,
+                ..
+            } => editor.left(),
+            KeyEvent {
+                code: KeyCode::Right,
+                ..
+            } => editor.right(),
+            KeyEvent {
+                code: KeyCode::Home,
+                ..
+            } => editor.home(),
+            KeyEvent {
+                code: KeyCode::End, ..
+            } => editor.end(),
+            KeyEvent {
+                code: KeyCode::Up, ..
+            } => editor.older(),
+            KeyEvent {
+                code: KeyCode::Down,
+                ..
+            } => editor.newer(),
+            _ => {}
+        }
+
+        render(&mut stdout, &editor);
+    }
+
+    disable_raw_mode().unwrap();
+}
@@ -0,0 +1,86 @@
+use std::time;
+
+// rosmsg_include works properly only with linux line end i.e. LF
+// it does now work properly with windows CR LF!!
+mod srv {
+    rosrust::rosmsg_include!(rosservices / ShapeMetrics);
+}
+
+fn usage() {
+    println!("usage: shape_metrics_client rectangle WIDTH LENGTH");
+    println!("       shape_metrics_client circle RADIUS");
+    println!("       shape_metrics_client triangle X1,Y1 X2,Y2 X3,Y3");
+    println!("       shape_metrics_client regular_polygon SIDES SIDE_LENGTH");
+    println!("       shape_metrics_client polygon X1,Y1 X2,Y2 X3,Y3 ...");
+}
+
+/// request from command line arguments (shape kind and its parameters)
+fn request(args: &[String]) -> Option<srv::rosservices::ShapeMetricsReq> {
+    let number = |i: usize| args.get(i)?.parse::<f64>().ok();
+
+    let mut req = srv::rosservices::ShapeMetricsReq {
+        kind: args.first()?.clone(),
+        ..Default::default()
+    };
+
+    match req.kind.as_str() {
+        "rectangle" if args.len() == 3 => {
+            req.width = number(1)?;
+            req.length = number(2)?;
+        }
+        "circle" if args.len() == 2 => req.radius = number(1)?,
+        "regular_polygon" if args.len() == 3 => {
+            req.sides = args[1].parse().ok()?;
+            req.side_length = number(2)?;
+        }
+        "triangle" | "polygon" => {
+            for point in &args[1..] {
+                let (x, y) = point.split_once(',')?;
+                req.x.push(x.parse().ok()?);
+                req.y.push(y.parse().ok()?);
+            }
+        }
+        _ => return None,
+    }
+
+    Some(req)
+}
+
+fn main() {
+    // Fetch args that are not meant for rosrust
+    let args: Vec<_> = rosrust::args();
+
+    let req = match request(&args[1..]) {
+        Some(req) => req,
+        None => {
+            usage();
+            return;
+        }
+    };
+
+    // Initialize node
+    rosrust::init("shape_metrics_client");
+
+    // Wait ten seconds for the service to appear
+    rosrust::ros_info!("checking for availability of shape_metrics service...");
+    rosrust::wait_for_service("shape_metrics", Some(time::Duration::from_secs(10))).unwrap();
+    rosrust::ros_info!("shape_metrics service found! calling...");
+
+    // Create client for the service
+    let client = rosrust::client::<srv::rosservices::ShapeMetrics>("shape_metrics").unwrap();
+
+    // Synchronous call that blocks the thread until a response is received
+    // outer Result is the transport error, inner one the error returned by the service
+    match client.req(&req) {
+        Ok(Ok(res)) => rosrust::ros_info!(
+            "{}: area = {}, perimeter = {}, centroid = ({}, {})",
+            req.kind,
+            res.area,
+            res.perimeter,
+            res.centroid_x,
+            res.centroid_y
+        ),
+        Ok(Err(e)) => rosrust::ros_err!("shape_metrics service error: {}", e),
+        Err(e) => rosrust::ros_err!("shape_metrics call failed: {}", e),
+    }
+}
@@ -0,0 +1,74 @@
+use ros_tutorial::shapes::Shape;
+
+// rosmsg_include works properly only with linux line end i.e. LF
+// it does now work properly with windows CR LF!!
+mod srv {
+    rosrust::rosmsg_include!(rosservices / ShapeMetrics);
+}
+
+/// shape described by the request
+fn shape_of(req: &srv::rosservices::ShapeMetricsReq) -> Result<Shape, String> {
+    if req.x.len() != req.y.len() {
+        return Err(format!(
+            "got {} x but {} y coordinates",
+            req.x.len(),
+            req.y.len()
+        ));
+    }
+    let vertices: Vec<(f64, f64)> = req.x.iter().copied().zip(req.y.iter().copied()).collect();
+
+    match req.kind.as_str() {
+        "rectangle" => Ok(Shape::Rectangle {
+            width: req.width,
+            length: req.length,
+        }),
+        "circle" => Ok(Shape::Circle { radius: req.radius }),
+        "triangle" => match vertices[..] {
+            [a, b, c] => Ok(Shape::Triangle([a, b, c])),
+            _ => Err(format!(
+                "triangle needs 3 vertices, got {}",
+                vertices.len()
+            )),
+        },
+        "regular_polygon" => Ok(Shape::RegularPolygon {
+            sides: req.sides,
+            side_length: req.side_length,
+        }),
+        "polygon" => Ok(Shape::Polygon(vertices)),
+        kind => Err(format!(
+            "unknown shape kind '{}', expected rectangle, circle, triangle, regular_polygon or polygon",
+            kind
+        )),
+    }
+}
+
+fn main() {
+    rosrust::init("shape_metrics_server");
+
+    // Create service
+    // The service is stopped when the returned object is destroyed
+    let _service_raii =
+        rosrust::service::<srv::rosservices::ShapeMetrics, _>("shape_metrics", move |req| {
+            // Err is sent back to the client as service error
+            match shape_of(&req).and_then(|shape| shape.metrics()) {
+                Ok(metrics) => {
+                    rosrust::ros_info!("{}: {:?}", req.kind, metrics);
+                    Ok(srv::rosservices::ShapeMetricsRes {
+                        area: metrics.area,
+                        perimeter: metrics.perimeter,
+                        centroid_x: metrics.centroid.0,
+                        centroid_y: metrics.centroid.1,
+                    })
+                }
+                Err(error) => {
+                    rosrust::ros_warn!("{}: {}", req.kind, error);
+                    Err(error)
+                }
+            }
+        })
+        .unwrap();
+
+    rosrust::ros_info!("shape_metrics service running!");
+
+    rosrust::spin();
+}
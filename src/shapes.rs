@@ -0,0 +1,301 @@
+//! Area, perimeter and centroid of shapes (backs the ShapeMetrics service).
+use std::f64::consts::PI;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Shape {
+    /// rectangle with corner at origin
+    Rectangle {
+        width: f64,
+        length: f64,
+    },
+    /// circle centered at origin
+    Circle {
+        radius: f64,
+    },
+    Triangle([(f64, f64); 3]),
+    /// regular polygon centered at origin
+    RegularPolygon {
+        sides: u32,
+        side_length: f64,
+    },
+    /// simple (not self-intersecting) polygon given by vertices
+    Polygon(Vec<(f64, f64)>),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Metrics {
+    pub area: f64,
+    pub perimeter: f64,
+    pub centroid: (f64, f64),
+}
+
+/// dimension must be finite positive number
+fn check_dimension(name: &str, value: f64) -> Result<(), String> {
+    if value.is_finite() && value > 0.0 {
+        Ok(())
+    } else {
+        Err(format!("{} must be positive, got {}", name, value))
+    }
+}
+
+/// cross product of vectors (b - a) and (c - a)
+fn cross(a: (f64, f64), b: (f64, f64), c: (f64, f64)) -> f64 {
+    (b.0 - a.0) * (c.1 - a.1) - (b.1 - a.1) * (c.0 - a.0)
+}
+
+/// true when segments a-b and c-d have common point
+fn segments_intersect(a: (f64, f64), b: (f64, f64), c: (f64, f64), d: (f64, f64)) -> bool {
+    let on_segment = |p: (f64, f64), q: (f64, f64), r: (f64, f64)| {
+        r.0 >= p.0.min(q.0) && r.0 <= p.0.max(q.0) && r.1 >= p.1.min(q.1) && r.1 <= p.1.max(q.1)
+    };
+
+    let d1 = cross(c, d, a);
+    let d2 = cross(c, d, b);
+    let d3 = cross(a, b, c);
+    let d4 = cross(a, b, d);
+
+    if ((d1 > 0.0 && d2 < 0.0) || (d1 < 0.0 && d2 > 0.0))
+        && ((d3 > 0.0 && d4 < 0.0) || (d3 < 0.0 && d4 > 0.0))
+    {
+        return true;
+    }
+
+    (d1 == 0.0 && on_segment(c, d, a))
+        || (d2 == 0.0 && on_segment(c, d, b))
+        || (d3 == 0.0 && on_segment(a, b, c))
+        || (d4 == 0.0 && on_segment(a, b, d))
+}
+
+/// metrics of simple polygon by shoelace formula
+fn polygon_metrics(vertices: &[(f64, f64)]) -> Result<Metrics, String> {
+    let n = vertices.len();
+    if n < 3 {
+        return Err(format!("polygon needs at least 3 vertices, got {}", n));
+    }
+    if vertices
+        .iter()
+        .any(|(x, y)| !x.is_finite() || !y.is_finite())
+    {
+        return Err(String::from("vertex coordinates must be finite numbers"));
+    }
+
+    let edge = |i: usize| (vertices[i], vertices[(i + 1) % n]);
+
+    for i in 0..n {
+        let (a, b) = edge(i);
+        if a == b {
+            return Err(format!("vertices {} and {} are the same", i, (i + 1) % n));
+        }
+
+        // edges sharing a vertex are not checked
+        for j in i + 2..n {
+            if i == 0 && j == n - 1 {
+                continue;
+            }
+            let (c, d) = edge(j);
+            if segments_intersect(a, b, c, d) {
+                return Err(format!(
+                    "polygon is self-intersecting (edges {} and {})",
+                    i, j
+                ));
+            }
+        }
+    }
+
+    let mut signed_area = 0.0;
+    let mut perimeter = 0.0;
+    let (mut cx, mut cy) = (0.0, 0.0);
+    for i in 0..n {
+        let ((x1, y1), (x2, y2)) = edge(i);
+        let cross = x1 * y2 - x2 * y1;
+        signed_area += cross / 2.0;
+        cx += (x1 + x2) * cross;
+        cy += (y1 + y2) * cross;
+        perimeter += (x2 - x1).hypot(y2 - y1);
+    }
+
+    if signed_area.abs() < f64::EPSILON * perimeter * perimeter {
+        return Err(String::from("shape is degenerate (zero area)"));
+    }
+
+    Ok(Metrics {
+        area: signed_area.abs(),
+        perimeter,
+        centroid: (cx / (6.0 * signed_area), cy / (6.0 * signed_area)),
+    })
+}
+
+impl Shape {
+    pub fn metrics(&self) -> Result<Metrics, String> {
+        let metrics = match self {
+            Shape::Rectangle { width, length } => {
+                check_dimension("width", *width)?;
+                check_dimension("length", *length)?;
+                Ok(Metrics {
+                    area: width * length,
+                    perimeter: 2.0 * (width + length),
+                    centroid: (width / 2.0, length / 2.0),
+                })
+            }
+
+            Shape::Circle { radius } => {
+                check_dimension("radius", *radius)?;
+                Ok(Metrics {
+                    area: PI * radius * radius,
+                    perimeter: 2.0 * PI * radius,
+                    centroid: (0.0, 0.0),
+                })
+            }
+
+            Shape::Triangle(vertices) => polygon_metrics(vertices),
+
+            Shape::RegularPolygon { sides, side_length } => {
+                if *sides < 3 {
+                    return Err(format!(
+                        "regular polygon needs at least 3 sides, got {}",
+                        sides
+                    ));
+                }
+                check_dimension("side length", *side_length)?;
+
+                let n = *sides as f64;
+                Ok(Metrics {
+                    area: n * side_length * side_length / (4.0 * (PI / n).tan()),
+                    perimeter: n * side_length,
+                    centroid: (0.0, 0.0),
+                })
+            }
+
+            Shape::Polygon(vertices) => polygon_metrics(vertices),
+        }?;
+
+        // finite inputs can still overflow, e.g. 1e200 x 1e200 rectangle
+        if [
+            metrics.area,
+            metrics.perimeter,
+            metrics.centroid.0,
+            metrics.centroid.1,
+        ]
+        .iter()
+        .all(|value| value.is_finite())
+        {
+            Ok(metrics)
+        } else {
+            Err(String::from("shape is too large, metrics overflow"))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn assert_metrics(shape: Shape, area: f64, perimeter: f64, centroid: (f64, f64)) {
+        let metrics = shape.metrics().unwrap();
+        assert!((metrics.area - area).abs() < 1e-9, "{:?}", metrics);
+        assert!(
+            (metrics.perimeter - perimeter).abs() < 1e-9,
+            "{:?}",
+            metrics
+        );
+        assert!(
+            (metrics.centroid.0 - centroid.0).abs() < 1e-9,
+            "{:?}",
+            metrics
+        );
+        assert!(
+            (metrics.centroid.1 - centroid.1).abs() < 1e-9,
+            "{:?}",
+            metrics
+        );
+    }
+
+    #[test]
+    fn test_metrics() {
+        assert_metrics(
+            Shape::Rectangle {
+                width: 2.5,
+                length: 4.0,
+            },
+            10.0,
+            13.0,
+            (1.25, 2.0),
+        );
+        assert_metrics(Shape::Circle { radius: 1.0 }, PI, 2.0 * PI, (0.0, 0.0));
+        assert_metrics(
+            Shape::Triangle([(0.0, 0.0), (4.0, 0.0), (0.0, 3.0)]),
+            6.0,
+            12.0,
+            (4.0 / 3.0, 1.0),
+        );
+        // square given as regular polygon
+        assert_metrics(
+            Shape::RegularPolygon {
+                sides: 4,
+                side_length: 2.0,
+            },
+            4.0,
+            8.0,
+            (0.0, 0.0),
+        );
+        // L shape, clockwise
+        assert_metrics(
+            Shape::Polygon(vec![
+                (0.0, 0.0),
+                (0.0, 2.0),
+                (1.0, 2.0),
+                (1.0, 1.0),
+                (2.0, 1.0),
+                (2.0, 0.0),
+            ]),
+            3.0,
+            8.0,
+            (5.0 / 6.0, 5.0 / 6.0),
+        );
+    }
+
+    #[test]
+    fn test_validation() {
+        assert!(Shape::Rectangle {
+            width: -1.0,
+            length: 2.0
+        }
+        .metrics()
+        .is_err());
+        assert!(Shape::Circle { radius: f64::NAN }.metrics().is_err());
+        assert!(Shape::RegularPolygon {
+            sides: 2,
+            side_length: 1.0
+        }
+        .metrics()
+        .is_err());
+        // collinear points
+        assert_eq!(
+            Shape::Triangle([(0.0, 0.0), (1.0, 1.0), (2.0, 2.0)]).metrics(),
+            Err(String::from("shape is degenerate (zero area)"))
+        );
+        assert!(Shape::Polygon(vec![(0.0, 0.0), (1.0, 0.0)])
+            .metrics()
+            .is_err());
+        assert!(Shape::Polygon(vec![(0.0, 0.0), (0.0, 0.0), (1.0, 1.0)])
+            .metrics()
+            .is_err());
+        assert_eq!(
+            Shape::Rectangle {
+                width: 1e200,
+                length: 1e200
+            }
+            .metrics(),
+            Err(String::from("shape is too large, metrics overflow"))
+        );
+        assert!(Shape::Polygon(vec![(0.0, 0.0), (1e300, 0.0), (0.0, 1e300)])
+            .metrics()
+            .is_err());
+        // bow tie
+        assert!(
+            Shape::Polygon(vec![(0.0, 0.0), (1.0, 1.0), (1.0, 0.0), (0.0, 1.0)])
+                .metrics()
+                .is_err()
+        );
+    }
+}
@@ -0,0 +1,576 @@
+//! Continuous keyboard teleoperation.
+//!
+//! Terminal reports only key presses (no releases). Holding a key produces
+//! auto-repeated presses, so key is considered as held while repeats keep coming
+//! and as released when they stop (deadman timeout). Velocity ramps up towards
+//! the speed requested by held keys and decays to zero once they are released.
+//!
+//! Terminal repeats only the key pressed last. Therefore when two keys of different
+//! axes are held (e.g. forward + rotate left for an arc), the earlier one is kept
+//! as held as long as the later one repeats.
+//!
+//! Keys are bound to actions by [Keymap], which can be loaded from a config file
+//! (see res/config/operator_keymap.conf).
+use crossterm::event::KeyCode;
+use std::collections::HashMap;
+use std::fs;
+use std::path::Path;
+use std::time::{Duration, Instant};
+
+/// speed scale is multiplied/divided by this factor by speed up/down actions
+const SPEED_SCALE_STEP: f64 = 1.1;
+
+/// motion requested by key
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Motion {
+    Forward,
+    Backward,
+    RotateLeft,
+    RotateRight,
+}
+
+impl Motion {
+    fn is_linear(&self) -> bool {
+        matches!(self, Motion::Forward | Motion::Backward)
+    }
+
+    fn opposite(&self) -> Motion {
+        match self {
+            Motion::Forward => Motion::Backward,
+            Motion::Backward => Motion::Forward,
+            Motion::RotateLeft => Motion::RotateRight,
+            Motion::RotateRight => Motion::RotateLeft,
+        }
+    }
+}
+
+/// action bound to key
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Action {
+    Move(Motion),
+    /// increase both linear and angular speed
+    SpeedUp,
+    /// decrease both linear and angular speed
+    SlowDown,
+    SpeedUpLinear,
+    SlowDownLinear,
+    SpeedUpAngular,
+    SlowDownAngular,
+    /// emergency stop
+    Stop,
+    /// control next discovered turtle
+    NextTurtle,
+    /// control n-th discovered turtle (1 based)
+    SelectTurtle(usize),
+    /// start/stop recording of published commands
+    ToggleRecording,
+    /// replay recorded commands once
+    Replay,
+    /// replay recorded commands in loop
+    ReplayLoop,
+}
+
+impl Action {
+    pub fn parse(name: &str) -> Option<Action> {
+        match name {
+            "forward" => Some(Action::Move(Motion::Forward)),
+            "backward" => Some(Action::Move(Motion::Backward)),
+            "rotate_left" => Some(Action::Move(Motion::RotateLeft)),
+            "rotate_right" => Some(Action::Move(Motion::RotateRight)),
+            "speed_up" => Some(Action::SpeedUp),
+            "slow_down" => Some(Action::SlowDown),
+            "speed_up_linear" => Some(Action::SpeedUpLinear),
+            "slow_down_linear" => Some(Action::SlowDownLinear),
+            "speed_up_angular" => Some(Action::SpeedUpAngular),
+            "slow_down_angular" => Some(Action::SlowDownAngular),
+            "stop" => Some(Action::Stop),
+            "next_turtle" => Some(Action::NextTurtle),
+            "toggle_recording" => Some(Action::ToggleRecording),
+            "replay" => Some(Action::Replay),
+            "replay_loop" => Some(Action::ReplayLoop),
+            _ => name
+                .strip_prefix("select_turtle_")
+                .and_then(|n| n.parse().ok())
+                .filter(|n| *n > 0)
+                .map(Action::SelectTurtle),
+        }
+    }
+
+    /// help line (0 motion, 1 speed, 2 turtle selection and macros) and description
+    fn help(&self) -> (usize, &'static str) {
+        match self {
+            Action::Move(Motion::Forward) => (0, "forward"),
+            Action::Move(Motion::Backward) => (0, "backward"),
+            Action::Move(Motion::RotateLeft) => (0, "rotate left"),
+            Action::Move(Motion::RotateRight) => (0, "rotate right"),
+            Action::SpeedUp => (1, "speed up"),
+            Action::SlowDown => (1, "slow down"),
+            Action::SpeedUpLinear => (1, "linear speed up"),
+            Action::SlowDownLinear => (1, "linear slow down"),
+            Action::SpeedUpAngular => (1, "angular speed up"),
+            Action::SlowDownAngular => (1, "angular slow down"),
+            Action::Stop => (1, "emergency stop"),
+            Action::NextTurtle => (2, "next turtle"),
+            Action::SelectTurtle(_) => (2, "select n-th turtle"),
+            Action::ToggleRecording => (2, "start/stop macro recording"),
+            Action::Replay => (2, "replay macro"),
+            Action::ReplayLoop => (2, "replay macro in loop"),
+        }
+    }
+}
+
+/// parses key name used in keymap file. single character (case sensitive)
+/// or one of: up, down, left, right, space, tab, enter, esc, backspace
+pub fn parse_key(name: &str) -> Option<KeyCode> {
+    let mut chars = name.chars();
+    if let (Some(c), None) = (chars.next(), chars.next()) {
+        return Some(KeyCode::Char(c));
+    }
+
+    match name.to_lowercase().as_str() {
+        "up" => Some(KeyCode::Up),
+        "down" => Some(KeyCode::Down),
+        "left" => Some(KeyCode::Left),
+        "right" => Some(KeyCode::Right),
+        "space" => Some(KeyCode::Char(' ')),
+        "tab" => Some(KeyCode::Tab),
+        "enter" => Some(KeyCode::Enter),
+        "esc" => Some(KeyCode::Esc),
+        "backspace" => Some(KeyCode::Backspace),
+        _ => None,
+    }
+}
+
+/// name of key as used in keymap file
+fn key_name(key: KeyCode) -> String {
+    match key {
+        KeyCode::Char(' ') => String::from("space"),
+        KeyCode::Char(c) => c.to_string(),
+        KeyCode::Up => String::from("up"),
+        KeyCode::Down => String::from("down"),
+        KeyCode::Left => String::from("left"),
+        KeyCode::Right => String::from("right"),
+        KeyCode::Tab => String::from("tab"),
+        KeyCode::Enter => String::from("enter"),
+        KeyCode::Esc => String::from("esc"),
+        KeyCode::Backspace => String::from("backspace"),
+        other => format!("{:?}", other),
+    }
+}
+
+/// key bindings of the operator
+#[derive(Debug, Clone, PartialEq)]
+pub struct Keymap {
+    /// in order of the keymap file (used by help)
+    bindings: Vec<(KeyCode, Action)>,
+}
+
+impl Default for Keymap {
+    /// arrows, WASD and vim keys (hjkl) for motion. speed keys follow teleop_twist_keyboard
+    /// (q/z all, e/c angular) except linear speed which is on W/X (w is taken by WASD).
+    fn default() -> Self {
+        Keymap::parse(include_str!("../res/config/operator_keymap.conf")).unwrap()
+    }
+}
+
+impl Keymap {
+    /// parses `key = action` lines. empty lines and lines starting with # are ignored
+    pub fn parse(content: &str) -> Result<Keymap, String> {
+        let mut bindings: Vec<(KeyCode, Action)> = vec![];
+
+        for (i, line) in content.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+
+            let (key, action) = line
+                .split_once('=')
+                .ok_or(format!("line {}: expected key = action", i + 1))?;
+            let key =
+                parse_key(key.trim()).ok_or(format!("line {}: unknown key {}", i + 1, key))?;
+            let action = Action::parse(action.trim()).ok_or(format!(
+                "line {}: unknown action {}",
+                i + 1,
+                action
+            ))?;
+
+            // later binding of the same key overrides the earlier one
+            bindings.retain(|(bound, _)| *bound != key);
+            bindings.push((key, action));
+        }
+
+        Ok(Keymap { bindings })
+    }
+
+    pub fn load(path: &Path) -> Result<Keymap, String> {
+        let content = fs::read_to_string(path).map_err(|e| format!("{:?}: {}", path, e))?;
+        Keymap::parse(&content)
+    }
+
+    pub fn action(&self, key: KeyCode) -> Option<Action> {
+        self.bindings
+            .iter()
+            .find(|(bound, _)| *bound == key)
+            .map(|(_, action)| *action)
+    }
+
+    /// help lines (motion, speed, turtle selection and macros) listing the bound keys,
+    /// e.g. "up/w/k: forward, down/s/j: backward, ..."
+    pub fn help(&self) -> [String; 3] {
+        // keys grouped by action description, in order of the keymap
+        let mut entries: Vec<(usize, &str, Vec<String>)> = vec![];
+        for (key, action) in &self.bindings {
+            let (line, description) = action.help();
+            match entries.iter_mut().find(|(_, d, _)| *d == description) {
+                Some((_, _, keys)) => keys.push(key_name(*key)),
+                None => entries.push((line, description, vec![key_name(*key)])),
+            }
+        }
+
+        let mut lines: [Vec<String>; 3] = Default::default();
+        for (line, description, keys) in entries {
+            lines[line].push(format!("{}: {}", keys.join("/"), description));
+        }
+        lines.map(|entries| entries.join(", "))
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct TeleopConfig {
+    /// linear speed (m/s) when forward/backward key is held
+    pub max_linear_speed: f64,
+    /// angular speed (rad/s) when rotation key is held
+    pub max_angular_speed: f64,
+    /// m/s^2 when speeding up
+    pub linear_acceleration: f64,
+    /// m/s^2 when slowing down (key released or direction changed)
+    pub linear_deceleration: f64,
+    /// rad/s^2 when speeding up
+    pub angular_acceleration: f64,
+    /// rad/s^2 when slowing down
+    pub angular_deceleration: f64,
+    /// time between key press and first auto-repeat of the terminal
+    pub repeat_delay: Duration,
+    /// key is released when no repeat arrives within this time
+    pub deadman_timeout: Duration,
+}
+
+impl Default for TeleopConfig {
+    fn default() -> Self {
+        TeleopConfig {
+            max_linear_speed: 1.0,
+            max_angular_speed: 0.5,
+            linear_acceleration: 2.0,
+            linear_deceleration: 4.0,
+            angular_acceleration: 2.0,
+            angular_deceleration: 4.0,
+            repeat_delay: Duration::from_millis(600),
+            deadman_timeout: Duration::from_millis(200),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+struct KeyHold {
+    last_event: Instant,
+    repeated: bool,
+}
+
+#[derive(Debug)]
+pub struct Teleop {
+    pub config: TeleopConfig,
+    held: HashMap<Motion, KeyHold>,
+    linear_speed: f64,
+    angular_speed: f64,
+    /// max_linear_speed multiplier changed at runtime
+    pub linear_scale: f64,
+    /// max_angular_speed multiplier changed at runtime
+    pub angular_scale: f64,
+}
+
+impl Teleop {
+    pub fn new(config: TeleopConfig) -> Teleop {
+        Teleop {
+            config,
+            held: HashMap::new(),
+            linear_speed: 0.0,
+            angular_speed: 0.0,
+            linear_scale: 1.0,
+            angular_scale: 1.0,
+        }
+    }
+
+    /// applies action triggered by key press
+    pub fn apply(&mut self, action: Action, now: Instant) {
+        match action {
+            Action::Move(motion) => self.key_pressed(motion, now),
+            Action::SpeedUp => {
+                self.linear_scale *= SPEED_SCALE_STEP;
+                self.angular_scale *= SPEED_SCALE_STEP;
+            }
+            Action::SlowDown => {
+                self.linear_scale /= SPEED_SCALE_STEP;
+                self.angular_scale /= SPEED_SCALE_STEP;
+            }
+            Action::SpeedUpLinear => self.linear_scale *= SPEED_SCALE_STEP,
+            Action::SlowDownLinear => self.linear_scale /= SPEED_SCALE_STEP,
+            Action::SpeedUpAngular => self.angular_scale *= SPEED_SCALE_STEP,
+            Action::SlowDownAngular => self.angular_scale /= SPEED_SCALE_STEP,
+            Action::Stop => self.stop(),
+            // turtle selection and macros are handled by the operator
+            Action::NextTurtle
+            | Action::SelectTurtle(_)
+            | Action::ToggleRecording
+            | Action::Replay
+            | Action::ReplayLoop => (),
+        }
+    }
+
+    /// (linear, angular) speed reached when keys are held with current scales
+    pub fn max_speeds(&self) -> (f64, f64) {
+        (
+            self.config.max_linear_speed * self.linear_scale,
+            self.config.max_angular_speed * self.angular_scale,
+        )
+    }
+
+    /// registers key press (or its auto-repeat)
+    pub fn key_pressed(&mut self, motion: Motion, now: Instant) {
+        // can not go forward and backward at once, opposite key must have been released
+        self.held.remove(&motion.opposite());
+
+        let repeated = self.held.contains_key(&motion);
+        self.held.insert(
+            motion,
+            KeyHold {
+                last_event: now,
+                repeated,
+            },
+        );
+    }
+
+    /// releases all keys and stops immediately (no deceleration)
+    pub fn stop(&mut self) {
+        self.held.clear();
+        self.linear_speed = 0.0;
+        self.angular_speed = 0.0;
+    }
+
+    /// keys considered as held at given time
+    pub fn held_motions(&self, now: Instant) -> Vec<Motion> {
+        let repeating: Vec<(Motion, KeyHold)> = self
+            .held
+            .iter()
+            .filter(|(_, hold)| {
+                let timeout = if hold.repeated {
+                    self.config.deadman_timeout
+                } else {
+                    self.config.repeat_delay
+                };
+                now.duration_since(hold.last_event) <= timeout
+            })
+            .map(|(motion, hold)| (*motion, *hold))
+            .collect();
+
+        let mut motions: Vec<Motion> = self
+            .held
+            .iter()
+            .filter(|(motion, hold)| {
+                repeating.iter().any(|(m, h)| {
+                    *m == **motion
+                        || (m.is_linear() != motion.is_linear() && h.last_event >= hold.last_event)
+                })
+            })
+            .map(|(motion, _)| *motion)
+            .collect();
+        motions.sort_by_key(|m| *m as u8);
+        motions
+    }
+
+    /// forgets released keys and ramps speeds towards requested ones.
+    /// returns (linear_speed, angular_speed) to be published
+    pub fn update(&mut self, now: Instant, dt: f64) -> (f64, f64) {
+        let held = self.held_motions(now);
+        self.held.retain(|motion, _| held.contains(motion));
+
+        let direction = |positive: Motion, negative: Motion| {
+            if held.contains(&positive) {
+                1.0
+            } else if held.contains(&negative) {
+                -1.0
+            } else {
+                0.0
+            }
+        };
+
+        let (max_linear_speed, max_angular_speed) = self.max_speeds();
+        let target_linear = max_linear_speed * direction(Motion::Forward, Motion::Backward);
+        let target_angular = max_angular_speed * direction(Motion::RotateLeft, Motion::RotateRight);
+
+        self.linear_speed = ramp(
+            self.linear_speed,
+            target_linear,
+            self.config.linear_acceleration,
+            self.config.linear_deceleration,
+            dt,
+        );
+        self.angular_speed = ramp(
+            self.angular_speed,
+            target_angular,
+            self.config.angular_acceleration,
+            self.config.angular_deceleration,
+            dt,
+        );
+
+        (self.linear_speed, self.angular_speed)
+    }
+}
+
+/// moves current speed towards target by at most acceleration * dt when speeding up
+/// or deceleration * dt when slowing down / reversing
+pub fn ramp(current: f64, target: f64, acceleration: f64, deceleration: f64, dt: f64) -> f64 {
+    let speeding_up = target.abs() > current.abs() && target * current >= 0.0;
+    let max_change = if speeding_up {
+        acceleration * dt
+    } else {
+        deceleration * dt
+    };
+
+    current + (target - current).clamp(-max_change, max_change)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_ramp() {
+        assert_eq!(ramp(0.0, 1.0, 2.0, 4.0, 0.1), 0.2);
+        assert_eq!(ramp(0.9, 1.0, 2.0, 4.0, 0.1), 1.0);
+        assert_eq!(ramp(1.0, 0.0, 2.0, 4.0, 0.1), 0.6);
+        assert_eq!(ramp(0.2, -1.0, 2.0, 4.0, 0.1), -0.2);
+        assert_eq!(ramp(-0.5, -0.5, 2.0, 4.0, 0.1), -0.5);
+    }
+
+    #[test]
+    fn test_hold_and_release() {
+        let mut teleop = Teleop::new(TeleopConfig::default());
+        let t0 = Instant::now();
+        let ms = |ms| t0 + Duration::from_millis(ms);
+
+        teleop.key_pressed(Motion::Forward, t0);
+        // before first auto-repeat key is still held and speed ramps up
+        let (linear, _) = teleop.update(ms(100), 0.1);
+        assert!((linear - 0.2).abs() < 1e-9);
+        assert_eq!(teleop.held_motions(ms(500)), vec![Motion::Forward]);
+
+        // auto-repeat
+        teleop.key_pressed(Motion::Forward, ms(600));
+        teleop.key_pressed(Motion::Forward, ms(630));
+        for i in 1..6 {
+            teleop.update(ms(630 + i * 10), 0.1);
+        }
+        assert_eq!(teleop.update(ms(700), 0.1), (1.0, 0.0));
+
+        // repeats stopped -> released after deadman timeout, speed decays
+        let (linear, _) = teleop.update(ms(900), 0.1);
+        assert!((linear - 0.6).abs() < 1e-9);
+        assert!(teleop.held_motions(ms(900)).is_empty());
+        teleop.update(ms(1000), 0.1);
+        assert_eq!(teleop.update(ms(1100), 0.1), (0.0, 0.0));
+    }
+
+    #[test]
+    fn test_keymap() {
+        let keymap = Keymap::default();
+        assert_eq!(
+            keymap.action(KeyCode::Up),
+            Some(Action::Move(Motion::Forward))
+        );
+        assert_eq!(
+            keymap.action(KeyCode::Char('h')),
+            Some(Action::Move(Motion::RotateLeft))
+        );
+        assert_eq!(
+            keymap.action(KeyCode::Char('W')),
+            Some(Action::SpeedUpLinear)
+        );
+        assert_eq!(keymap.action(KeyCode::Char(' ')), Some(Action::Stop));
+        assert_eq!(keymap.action(KeyCode::Char('P')), Some(Action::ReplayLoop));
+        assert_eq!(keymap.action(KeyCode::Char('m')), None);
+
+        let keymap = Keymap::parse("# custom\n\ni = forward\nTab = stop\n").unwrap();
+        assert_eq!(
+            keymap.action(KeyCode::Char('i')),
+            Some(Action::Move(Motion::Forward))
+        );
+        assert_eq!(keymap.action(KeyCode::Tab), Some(Action::Stop));
+        assert_eq!(keymap.action(KeyCode::Up), None);
+
+        assert_eq!(
+            Keymap::parse("0 = select_turtle_10")
+                .unwrap()
+                .action(KeyCode::Char('0')),
+            Some(Action::SelectTurtle(10))
+        );
+        assert!(Keymap::parse("0 = select_turtle_0").is_err());
+        assert!(Keymap::parse("i = fly").is_err());
+        assert!(Keymap::parse("pagedown = stop").is_err());
+        assert!(Keymap::parse("i forward").is_err());
+
+        let help = Keymap::default().help();
+        assert!(help[0].starts_with("up/w/k: forward, down/s/j: backward"));
+        assert!(help[1].ends_with("space: emergency stop"));
+        assert!(help[2].contains("1/2/3/4/5/6/7/8/9: select n-th turtle"));
+        let help = Keymap::parse("i = forward\nx = stop\ni = backward")
+            .unwrap()
+            .help();
+        assert_eq!(help[0], "i: backward");
+        assert_eq!(help[1], "x: emergency stop");
+        assert_eq!(help[2], "");
+    }
+
+    #[test]
+    fn test_speed_scaling() {
+        let mut teleop = Teleop::new(TeleopConfig::default());
+        let now = Instant::now();
+
+        teleop.apply(Action::SpeedUp, now);
+        teleop.apply(Action::SlowDownAngular, now);
+        let (linear, angular) = teleop.max_speeds();
+        assert!((linear - 1.1).abs() < 1e-9);
+        assert!((angular - 0.5).abs() < 1e-9);
+
+        teleop.apply(Action::Move(Motion::Forward), now);
+        teleop.update(now, 1.0);
+        teleop.apply(Action::Stop, now);
+        assert_eq!(teleop.update(now, 0.1), (0.0, 0.0));
+    }
+
+    #[test]
+    fn test_arc() {
+        let mut teleop = Teleop::new(TeleopConfig::default());
+        let t0 = Instant::now();
+        let ms = |ms| t0 + Duration::from_millis(ms);
+
+        teleop.key_pressed(Motion::Forward, t0);
+        teleop.key_pressed(Motion::Forward, ms(600));
+        // rotation key pressed while forward is held, terminal repeats only rotation now
+        teleop.key_pressed(Motion::RotateLeft, ms(700));
+        teleop.key_pressed(Motion::RotateLeft, ms(1300));
+        teleop.key_pressed(Motion::RotateLeft, ms(1400));
+
+        assert_eq!(
+            teleop.held_motions(ms(1500)),
+            vec![Motion::Forward, Motion::RotateLeft]
+        );
+
+        // opposite key replaces the held one
+        teleop.key_pressed(Motion::Backward, ms(1450));
+        assert_eq!(
+            teleop.held_motions(ms(1500)),
+            vec![Motion::Backward, Motion::RotateLeft]
+        );
+    }
+}
@@ -0,0 +1,103 @@
+use ros_tutorial::recording::{Playback, Recording};
+use rosrust::ros_info;
+use rosrust_msg::geometry_msgs::Twist;
+use std::path::Path;
+use std::thread;
+use std::time::{Duration, Instant};
+
+/// replays macro recorded by turtle_operator with the original timing
+fn main() {
+    // Fetch args that are not meant for rosrust
+    let args: Vec<_> = rosrust::args();
+
+    let usage = || {
+        println!("usage: teleop_replay MACRO_FILE [SPEED_MULTIPLIER] [LOOP] [TURTLE]");
+        println!("example: teleop_replay teleop_macro.csv 2.0 true /turtle2");
+    };
+
+    if args.len() < 2 || args.len() > 5 {
+        return usage();
+    }
+
+    let speed = match args.get(2).map(|a| a.parse::<f64>()) {
+        None => 1.0,
+        Some(Ok(speed)) if speed.is_finite() && speed > 0.0 => speed,
+        _ => {
+            println!("speed multiplier must be positive number");
+            return usage();
+        }
+    };
+    let mut looping = match args.get(3).map(|a| a.parse::<bool>()) {
+        None => false,
+        Some(Ok(looping)) => looping,
+        Some(Err(_)) => {
+            println!("loop must be true or false");
+            return usage();
+        }
+    };
+    let turtle = args.get(4).map(String::as_str).unwrap_or("/turtle1");
+
+    let recording = match Recording::load(Path::new(&args[1])) {
+        Ok(recording) if recording.commands.is_empty() => {
+            println!("macro {} is empty, nothing to replay", args[1]);
+            return;
+        }
+        Ok(recording) => recording,
+        Err(e) => {
+            println!("unable to load macro: {}", e);
+            return;
+        }
+    };
+
+    // all commands of the recording are sent at once, looping would only spin
+    if looping && recording.duration().is_zero() {
+        println!("macro {} has zero duration, it is replayed once", args[1]);
+        looping = false;
+    }
+
+    rosrust::init("teleop_replay");
+
+    let publisher = rosrust::publish::<Twist>(&format!("{}/cmd_vel", turtle), 100).unwrap();
+
+    // give subscribers some time to connect, otherwise first commands would be lost
+    let t0 = Instant::now();
+    while publisher.subscriber_count() == 0 && t0.elapsed() < Duration::from_secs(2) {
+        thread::sleep(Duration::from_millis(50));
+    }
+
+    let playback = Playback::new(recording, speed, looping, Instant::now());
+    ros_info!(
+        "replaying {} commands ({:.1}s) to {}/cmd_vel. speed: {} loop: {}",
+        playback.recording.commands.len(),
+        playback.recording.duration().as_secs_f64() / speed,
+        turtle,
+        speed,
+        looping
+    );
+
+    loop {
+        let pass_started = Instant::now();
+        for (offset, linear_speed, angular_speed) in playback.schedule() {
+            if !rosrust::is_ok() {
+                break;
+            }
+
+            let send_at = pass_started + offset;
+            if let Some(wait) = send_at.checked_duration_since(Instant::now()) {
+                thread::sleep(wait);
+            }
+
+            let mut msg = Twist::default();
+            msg.linear.x = linear_speed;
+            msg.angular.z = angular_speed;
+            publisher.send(msg).unwrap();
+        }
+
+        if !looping || !rosrust::is_ok() {
+            break;
+        }
+    }
+
+    publisher.send(Twist::default()).unwrap();
+    ros_info!("replay finished");
+}
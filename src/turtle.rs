@@ -1,3 +1,4 @@
+use ros_tutorial::battery::{simulate_battery, BatteryModel};
 use rosrust_msg::geometry_msgs::Twist;
 use std::cell::RefCell;
 
@@ -26,13 +27,22 @@ fn main() {
         // dummy: no real calculation here! we are not
         // really maintaining turtle position here
         // that would require some math ;)
-        position.x = position.x + t.linear.x as f32;
-        position.y = position.y + t.linear.y as f32;
+        position.x += t.linear.x as f32;
+        position.y += t.linear.y as f32;
 
         rosrust::ros_info!("new position: {:?}", position);
         ros_publisher.send(position.clone()).unwrap();
     })
     .unwrap();
 
+    // with --battery, battery is drained by commands received on /turtle1/cmd_vel and its
+    // state is published on /turtle1/battery_state. don't combine with battery simulated
+    // by turtle_cleaner (mode 10), each would publish its own charge.
+    let _battery = if rosrust::args().iter().any(|arg| arg == "--battery") {
+        Some(simulate_battery("/turtle1", BatteryModel::default(), 10.0))
+    } else {
+        None
+    };
+
     rosrust::spin();
 }
@@ -9,80 +9,185 @@ use lazy_static::lazy_static;
 use rosrust::Publisher;
 use rosrust::{ros_debug, ros_err, ros_info};
 use rosrust_msg::geometry_msgs::Twist;
+use rosrust_msg::sensor_msgs::BatteryState;
+use rosrust_msg::std_msgs::Bool;
 
-#[cfg(not(feature = "strict-pos-sync"))]
-use std::sync::RwLock;
+use ros_tutorial::battery::{simulate_battery, BatteryModel};
+use ros_tutorial::discovery::turtle_namespaces;
+
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::f64::consts::PI;
+use std::sync::{Arc, RwLock};
 
 #[cfg(feature = "strict-pos-sync")]
 use std::sync::Mutex;
 
+use std::fs;
+use std::io;
+use std::path::Path;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::thread;
 use std::time::SystemTime;
 
 mod msg {
     rosrust::rosmsg_include!(turtlesim / Pose);
 }
 
-#[derive(Default, Debug)]
+#[derive(Default, Debug, Clone)]
 struct TurtlePosition {
     pub x: f64,
     pub y: f64,
     pub yaw: f64,
 }
 
+/// namespace of turtle controlled when no other is specified
+const DEFAULT_TURTLE: &str = "/turtle1";
+
+// positions of all known turtles, key is turtle namespace (e.g. /turtle1)
 #[cfg(not(feature = "strict-pos-sync"))]
 lazy_static! {
-    static ref TURTLE_POSITION: RwLock<TurtlePosition> = RwLock::new(TurtlePosition::default());
+    static ref TURTLE_POSITIONS: RwLock<HashMap<String, TurtlePosition>> =
+        RwLock::new(HashMap::new());
 }
 
 #[cfg(feature = "strict-pos-sync")]
 lazy_static! {
-    static ref TURTLE_POSITION: Mutex<TurtlePosition> = Mutex::new(TurtlePosition::default());
+    static ref TURTLE_POSITIONS: Mutex<HashMap<String, TurtlePosition>> =
+        Mutex::new(HashMap::new());
 }
 
-/// helper function to get current turtle position
+thread_local! {
+    /// turtle controlled by current thread. motion functions below work with
+    /// position of this turtle, so that each thread can drive different turtle.
+    static CURRENT_TURTLE: RefCell<String> = RefCell::new(DEFAULT_TURTLE.to_string());
+}
+
+/// namespace of turtle controlled by current thread
+fn current_turtle() -> String {
+    CURRENT_TURTLE.with(|t| t.borrow().clone())
+}
+
+/// binds current thread to turtle with given namespace
+fn set_current_turtle(namespace: &str) {
+    CURRENT_TURTLE.with(|t| *t.borrow_mut() = namespace.to_string());
+}
+
+/// helper function to get position of turtle with given namespace. None until first pose is received
 #[cfg(not(feature = "strict-pos-sync"))]
-fn get_current_position() -> TurtlePosition {
-    let rl = TURTLE_POSITION.read().unwrap();
-    let turtle_position = &*rl;
+fn find_position(namespace: &str) -> Option<TurtlePosition> {
+    let rl = TURTLE_POSITIONS.read().unwrap();
+    rl.get(namespace).cloned()
+}
 
-    TurtlePosition {
-        x: turtle_position.x,
-        y: turtle_position.y,
-        yaw: turtle_position.yaw,
-    }
+/// helper function to set position of turtle with given namespace
+#[cfg(not(feature = "strict-pos-sync"))]
+fn set_position(namespace: &str, new_turtle_position: &TurtlePosition) {
+    let mut wl = TURTLE_POSITIONS.write().unwrap();
+    wl.insert(namespace.to_string(), new_turtle_position.clone());
+}
+
+/// helper function to get position of turtle with given namespace. None until first pose is received
+#[cfg(feature = "strict-pos-sync")]
+fn find_position(namespace: &str) -> Option<TurtlePosition> {
+    let rl = TURTLE_POSITIONS.lock().unwrap();
+    rl.get(namespace).cloned()
 }
 
-/// helper function to set current turtle position
+/// helper function to set position of turtle with given namespace
+#[cfg(feature = "strict-pos-sync")]
+fn set_position(namespace: &str, new_turtle_position: &TurtlePosition) {
+    let mut wl = TURTLE_POSITIONS.lock().unwrap();
+    wl.insert(namespace.to_string(), new_turtle_position.clone());
+}
+
+/// helper function to get positions of all known turtles
 #[cfg(not(feature = "strict-pos-sync"))]
-fn set_current_position(new_turtle_position: &TurtlePosition) {
-    let mut wl = TURTLE_POSITION.write().unwrap();
-    let turtle_position = &mut *wl;
-    turtle_position.x = new_turtle_position.x;
-    turtle_position.y = new_turtle_position.y;
-    turtle_position.yaw = new_turtle_position.yaw;
+fn get_all_positions() -> HashMap<String, TurtlePosition> {
+    TURTLE_POSITIONS.read().unwrap().clone()
 }
 
-/// helper function to get current turtle position
+/// helper function to get positions of all known turtles
 #[cfg(feature = "strict-pos-sync")]
+fn get_all_positions() -> HashMap<String, TurtlePosition> {
+    TURTLE_POSITIONS.lock().unwrap().clone()
+}
+
+/// helper function to get position of turtle with given namespace
+fn get_position(namespace: &str) -> TurtlePosition {
+    find_position(namespace).unwrap_or_default()
+}
+
+/// helper function to get current turtle position
 fn get_current_position() -> TurtlePosition {
-    let rl = TURTLE_POSITION.lock().unwrap();
-    let turtle_position = &*rl;
+    get_position(&current_turtle())
+}
 
-    TurtlePosition {
-        x: turtle_position.x,
-        y: turtle_position.y,
-        yaw: turtle_position.yaw,
-    }
+/// subscribes to pose of turtle with given namespace and keeps its position up to date
+fn subscribe_position(namespace: &str) -> rosrust::Subscriber {
+    let ns = namespace.to_string();
+    rosrust::subscribe(
+        &format!("{}/pose", namespace),
+        100,
+        move |pose: msg::turtlesim::Pose| {
+            set_position(
+                &ns,
+                &TurtlePosition {
+                    x: pose.x as f64,
+                    y: pose.y as f64,
+                    yaw: pose.theta as f64,
+                },
+            );
+        },
+    )
+    .unwrap()
 }
 
-/// helper function to set current turtle position
-#[cfg(feature = "strict-pos-sync")]
-fn set_current_position(new_turtle_position: &TurtlePosition) {
-    let mut wl = TURTLE_POSITION.lock().unwrap();
-    let turtle_position = &mut *wl;
-    turtle_position.x = new_turtle_position.x;
-    turtle_position.y = new_turtle_position.y;
-    turtle_position.yaw = new_turtle_position.yaw;
+lazy_static! {
+    /// last battery charge (0.0 - 1.0) received on <cleaning turtle>/battery_state. None until first message arrives
+    static ref BATTERY_LEVEL: RwLock<Option<f64>> = RwLock::new(None);
+}
+
+/// subscribes battery state of turtle with given namespace, see get_battery_level
+fn subscribe_battery(namespace: &str) -> rosrust::Subscriber {
+    rosrust::subscribe(
+        &format!("{}/battery_state", namespace),
+        10,
+        move |battery: BatteryState| {
+            *BATTERY_LEVEL.write().unwrap() = Some(battery.percentage as f64);
+        },
+    )
+    .unwrap()
+}
+
+/// helper function to get last known battery charge
+fn get_battery_level() -> Option<f64> {
+    *BATTERY_LEVEL.read().unwrap()
+}
+
+/// set by /turtle_cleaner/pause topic. while true coverage routines hold the turtle still
+static CLEANING_PAUSED: AtomicBool = AtomicBool::new(false);
+
+/// helper function to check whether cleaning was paused
+fn is_paused() -> bool {
+    CLEANING_PAUSED.load(Ordering::SeqCst)
+}
+
+/// stops the turtle and blocks until cleaning is resumed (or node is shut down)
+fn wait_while_paused(velocity_publisher: &mut Publisher<Twist>) {
+    if !is_paused() {
+        return;
+    }
+
+    ros_info!("cleaning paused. current pos: {:?}", get_current_position());
+    velocity_publisher.send(Twist::default()).unwrap();
+
+    let loop_rate = rosrust::rate(10.0);
+    while is_paused() && rosrust::is_ok() {
+        loop_rate.sleep();
+    }
+
+    ros_info!("cleaning resumed");
 }
 
 /// calculates euclidean distance of two points in 2D ( [x0,y0] and [x1, y1] )
@@ -113,9 +218,173 @@ fn angle_to_target_2d(x_current: f64, y_current: f64, x_target: f64, y_target: f
     (y_target - y_current).atan2(x_target - x_current)
 }
 
+/// normalizes angle into range (-PI, PI]
+fn normalize_angle(angle: f64) -> f64 {
+    let mut angle = angle % (2.0 * PI);
+    if angle > PI {
+        angle -= 2.0 * PI;
+    } else if angle <= -PI {
+        angle += 2.0 * PI;
+    }
+    angle
+}
+
+/// turtle as seen by collision avoidance
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct Agent {
+    pub x: f64,
+    pub y: f64,
+    pub yaw: f64,
+    /// lower value means higher priority
+    pub priority: u32,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct AvoidanceParams {
+    /// distance between turtles (centers) which is never violated
+    pub min_separation: f64,
+    /// time (in seconds) in which turtle may close the gap to min_separation.
+    /// must not be shorter than period of control loops (0.1 s)
+    pub horizon: f64,
+    /// angular speed used to turn away from turtle blocking the way
+    pub yield_angular_speed: f64,
+}
+
+impl Default for AvoidanceParams {
+    fn default() -> Self {
+        AvoidanceParams {
+            min_separation: 0.8,
+            horizon: 0.5,
+            yield_angular_speed: 1.0,
+        }
+    }
+}
+
+/// turtles are prioritized by number in their namespace (/turtle1 has the highest priority)
+fn turtle_priority(namespace: &str) -> u32 {
+    let digits: String = namespace
+        .chars()
+        .rev()
+        .take_while(|c| c.is_ascii_digit())
+        .collect::<Vec<_>>()
+        .into_iter()
+        .rev()
+        .collect();
+    digits.parse().unwrap_or(0)
+}
+
+/// Reciprocal collision avoidance. Limits linear speed of own turtle so that it approaches
+/// every other turtle at most by its share of the gap (distance - min_separation) within
+/// the horizon. Shares of both turtles sum up to 1 (higher priority turtle takes 1/4 of the
+/// responsibility, lower priority 3/4, equal priority 1/2), so as long as every turtle applies
+/// this filter the gap can not be closed and min_separation is kept.
+///
+/// Turtle which is blocked (can not move forward anymore) turns away from the blocking turtle.
+/// In head-on situation both turtles turn right, which resolves the deadlock.
+///
+/// returns adjusted (linear_speed, angular_speed)
+fn avoid_collisions(
+    own: &Agent,
+    others: &[Agent],
+    linear_speed: f64,
+    angular_speed: f64,
+    params: &AvoidanceParams,
+) -> (f64, f64) {
+    let mut linear_speed = linear_speed;
+    let mut angular_speed = angular_speed;
+    let requested_speed = linear_speed.abs();
+
+    for other in others {
+        let distance = calculate_distance_2d(own.x, own.y, other.x, other.y);
+        let bearing = normalize_angle(angle_to_target_2d(own.x, own.y, other.x, other.y) - own.yaw);
+        let closing_speed = linear_speed * bearing.cos();
+
+        if closing_speed <= 0.0 {
+            continue;
+        }
+
+        let share = match own.priority.cmp(&other.priority) {
+            std::cmp::Ordering::Less => 0.25,
+            std::cmp::Ordering::Equal => 0.5,
+            std::cmp::Ordering::Greater => 0.75,
+        };
+        let allowed_closing_speed =
+            share * (distance - params.min_separation).max(0.0) / params.horizon;
+
+        if closing_speed > allowed_closing_speed {
+            linear_speed = allowed_closing_speed / bearing.cos();
+
+            if linear_speed.abs() < 0.1 * requested_speed {
+                angular_speed = if bearing >= 0.0 {
+                    -params.yield_angular_speed
+                } else {
+                    params.yield_angular_speed
+                };
+            }
+        }
+    }
+
+    (linear_speed, angular_speed)
+}
+
+lazy_static! {
+    /// collision avoidance applied to all velocity commands sent by send_velocity. None = disabled
+    static ref COLLISION_AVOIDANCE: RwLock<Option<AvoidanceParams>> = RwLock::new(None);
+}
+
+/// sends velocity command of turtle controlled by current thread. if collision avoidance is
+/// enabled command is adjusted to keep safe distance from all other known turtles.
+fn send_velocity(velocity_publisher: &Publisher<Twist>, velocity_msg: Twist) {
+    let mut velocity_msg = velocity_msg;
+    let params = *COLLISION_AVOIDANCE.read().unwrap();
+
+    if let Some(params) = params {
+        let namespace = current_turtle();
+        if let Some(position) = find_position(&namespace) {
+            let own = Agent {
+                x: position.x,
+                y: position.y,
+                yaw: position.yaw,
+                priority: turtle_priority(&namespace),
+            };
+            let others: Vec<Agent> = get_all_positions()
+                .iter()
+                .filter(|(ns, _)| **ns != namespace)
+                .map(|(ns, p)| Agent {
+                    x: p.x,
+                    y: p.y,
+                    yaw: p.yaw,
+                    priority: turtle_priority(ns),
+                })
+                .collect();
+
+            let (linear_speed, angular_speed) = avoid_collisions(
+                &own,
+                &others,
+                velocity_msg.linear.x,
+                velocity_msg.angular.z,
+                &params,
+            );
+            if linear_speed != velocity_msg.linear.x {
+                ros_debug!(
+                    "{} avoiding collision. linear speed {} -> {}",
+                    namespace,
+                    velocity_msg.linear.x,
+                    linear_speed
+                );
+            }
+            velocity_msg.linear.x = linear_speed;
+            velocity_msg.angular.z = angular_speed;
+        }
+    }
+
+    velocity_publisher.send(velocity_msg).unwrap();
+}
+
 /// moves forward (by publishing Twist messages with given linear speed)
 /// until defined distance is travelled by defined speed
 /// then linear speed is set to zero to stop.
+/// NOTE: distance is estimated from time, so it is not precise when collision avoidance slows the turtle down
 fn move_forward(
     velocity_publisher: &mut Publisher<Twist>,
     speed: f64,
@@ -133,11 +402,11 @@ fn move_forward(
     let loop_rate = rosrust::rate(10.0); // 10 Hz loop rate
     let mut current_distance = 0.0;
     while current_distance < distance {
-        velocity_publisher.send(velocity_msg.clone()).unwrap();
+        send_velocity(velocity_publisher, velocity_msg.clone());
 
         match t0.elapsed() {
             Ok(elapsed) => {
-                current_distance = speed * elapsed.as_secs_f64() as f64;
+                current_distance = speed * elapsed.as_secs_f64();
                 ros_debug!("moved: {}/{}", current_distance, distance);
             }
             Err(e) => {
@@ -170,9 +439,9 @@ fn rotate(
 
     let mut current_angle = 0.0;
     loop {
-        velocity_publisher.send(velocity_msg.clone()).unwrap();
+        send_velocity(velocity_publisher, velocity_msg.clone());
 
-        let time_elapsed = t0.elapsed().unwrap().as_secs_f64() as f64;
+        let time_elapsed = t0.elapsed().unwrap().as_secs_f64();
 
         if current_angle > rotation_rad {
             break;
@@ -200,6 +469,29 @@ fn rotate(
     velocity_publisher.send(velocity_msg).unwrap();
 }
 
+/// proportional controller steering follower at given position towards its formation
+/// target. linear speed is proportional to target distance, angular speed to the difference
+/// between current yaw and angle to target. unlike in go_to_target the difference is
+/// normalized: moving target often crosses the +-PI boundary, follower must turn the shorter way.
+/// returns (linear_speed, angular_speed)
+fn follower_controller(
+    turtle_position: &TurtlePosition,
+    target_x: f64,
+    target_y: f64,
+    k_linear: f64,
+    k_angular: f64,
+) -> (f64, f64) {
+    let target_distance =
+        calculate_distance_2d(turtle_position.x, turtle_position.y, target_x, target_y);
+    let angle_to_target =
+        angle_to_target_2d(turtle_position.x, turtle_position.y, target_x, target_y);
+
+    (
+        k_linear * target_distance,
+        k_angular * normalize_angle(angle_to_target - turtle_position.yaw),
+    )
+}
+
 /// moves from current position to target position
 /// linear and angular speed is proportionally controlled
 /// by k_linear and k_angular constants. To closer to target
@@ -231,13 +523,14 @@ fn go_to_target(
         velocity_msg.linear.x = linear_speed;
         velocity_msg.angular.z = angular_speed;
 
-        velocity_publisher.send(velocity_msg.clone()).unwrap();
+        send_velocity(velocity_publisher, velocity_msg.clone());
 
-        if target_distance < 0.01 {
+        if target_distance < 0.01 || !rosrust::is_ok() {
             break;
         }
 
         loop_rate.sleep();
+        wait_while_paused(velocity_publisher);
     }
 
     velocity_msg.linear.x = 0.0;
@@ -254,7 +547,7 @@ fn set_yaw(velocity_publisher: &mut Publisher<Twist>, angular_speed: f64, new_ya
         return;
     }
 
-    let clockwise = if angle_to_rotate < 0.0 { true } else { false };
+    let clockwise = angle_to_rotate < 0.0;
     rotate(
         velocity_publisher,
         angular_speed,
@@ -280,11 +573,11 @@ fn spiral_move(
         if turtle_position.x > 10.5 || turtle_position.y > 10.5 {
             break;
         }
-        linear_speed = linear_speed + 1.0;
+        linear_speed += 1.0;
         velocity_msg.linear.x = linear_speed;
         velocity_msg.angular.z = angular_speed;
 
-        velocity_publisher.send(velocity_msg.clone()).unwrap();
+        send_velocity(velocity_publisher, velocity_msg.clone());
         loop_rate.sleep();
     }
 
@@ -293,6 +586,565 @@ fn spiral_move(
     velocity_publisher.send(velocity_msg).unwrap();
 }
 
+/// single point of coverage path
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct Waypoint {
+    pub x: f64,
+    pub y: f64,
+}
+
+/// lawnmower path used by grid cleaning. starts in [1, 1] and sweeps
+/// the arena column by column (south -> north -> south ...)
+fn grid_waypoints() -> Vec<Waypoint> {
+    let mut waypoints = vec![Waypoint { x: 1.0, y: 1.0 }];
+
+    for i in (2..5).step_by(1) {
+        let x = i as f64;
+        waypoints.push(Waypoint { x, y: 1.0 });
+        waypoints.push(Waypoint { x, y: 10.0 });
+        waypoints.push(Waypoint {
+            x: x + 1.0,
+            y: 10.0,
+        });
+        waypoints.push(Waypoint { x: x + 1.0, y: 1.0 });
+    }
+
+    waypoints
+}
+
+/// progress of coverage run. persisted after every reached waypoint
+/// so that interrupted run (e.g. battery swap) can be resumed later.
+/// step is index of first waypoint which was not reached yet.
+#[derive(Debug, Default, PartialEq)]
+struct Checkpoint {
+    pub step: usize,
+    pub x: f64,
+    pub y: f64,
+    pub yaw: f64,
+    pub elapsed_secs: f64,
+}
+
+impl Checkpoint {
+    /// parses checkpoint from simple key=value lines
+    fn parse(content: &str) -> Option<Checkpoint> {
+        let mut checkpoint = Checkpoint::default();
+
+        for line in content.lines().map(str::trim).filter(|l| !l.is_empty()) {
+            let (key, value) = line.split_once('=')?;
+            let value = value.trim();
+            match key.trim() {
+                "step" => checkpoint.step = value.parse().ok()?,
+                "x" => checkpoint.x = value.parse().ok()?,
+                "y" => checkpoint.y = value.parse().ok()?,
+                "yaw" => checkpoint.yaw = value.parse().ok()?,
+                "elapsed_secs" => checkpoint.elapsed_secs = value.parse().ok()?,
+                _ => return None,
+            }
+        }
+
+        Some(checkpoint)
+    }
+
+    fn serialize(&self) -> String {
+        format!(
+            "step={}\nx={}\ny={}\nyaw={}\nelapsed_secs={}\n",
+            self.step, self.x, self.y, self.yaw, self.elapsed_secs
+        )
+    }
+
+    /// loads checkpoint from file. None if file does not exist or is corrupted
+    fn load(path: &Path) -> Option<Checkpoint> {
+        let content = fs::read_to_string(path).ok()?;
+        let checkpoint = Checkpoint::parse(&content);
+        if checkpoint.is_none() {
+            ros_err!("ignoring corrupted checkpoint file {:?}", path);
+        }
+        checkpoint
+    }
+
+    /// writes into temporary file first and then renames it so that
+    /// interruption in the middle of write never leaves half written checkpoint
+    fn save(&self, path: &Path) -> io::Result<()> {
+        let tmp_path = path.with_extension("tmp");
+        fs::write(&tmp_path, self.serialize())?;
+        fs::rename(&tmp_path, path)
+    }
+}
+
+/// tolerance (in meters) under which waypoint is considered as reached
+const WAYPOINT_TOLERANCE: f64 = 0.1;
+
+/// returns index of waypoint where interrupted run should continue. this is the
+/// uncovered waypoint (from checkpoint step on) nearest to the turtle, so the turtle
+/// does not drive back when it was moved, e.g. to battery swap. the waypoint is
+/// skipped if turtle already stands on it.
+fn resume_step(waypoints: &[Waypoint], checkpoint: &Checkpoint, x: f64, y: f64) -> usize {
+    let step = checkpoint.step.min(waypoints.len());
+    let distance = |w: &Waypoint| calculate_distance_2d(x, y, w.x, w.y);
+
+    let nearest = waypoints[step..]
+        .iter()
+        .enumerate()
+        .min_by(|(_, a), (_, b)| distance(a).total_cmp(&distance(b)));
+
+    match nearest {
+        Some((i, w)) if distance(w) < WAYPOINT_TOLERANCE => step + i + 1,
+        Some((i, _)) => step + i,
+        None => step,
+    }
+}
+
+/// charging station. when battery drops below low_level coverage is interrupted,
+/// turtle goes to dock and waits there until battery is charged to resume_level
+#[derive(Debug, Clone)]
+struct Dock {
+    pub x: f64,
+    pub y: f64,
+    pub low_level: f64,
+    pub resume_level: f64,
+}
+
+/// optional behaviours of coverage run
+#[derive(Debug, Default)]
+struct CoverageOptions<'a> {
+    /// progress is stored into/resumed from this file
+    pub checkpoint_path: Option<&'a Path>,
+    /// return to dock when battery is low
+    pub dock: Option<Dock>,
+}
+
+/// goes to dock, starts charging and waits until battery is charged enough.
+/// returns false if node was shut down in the meantime.
+fn recharge(velocity_publisher: &mut Publisher<Twist>, dock: &Dock) -> bool {
+    ros_info!(
+        "battery low ({:?}). going to dock [{}, {}]",
+        get_battery_level(),
+        dock.x,
+        dock.y
+    );
+    go_to_target(velocity_publisher, dock.x, dock.y, 0.5, 4.0);
+
+    let charging_publisher =
+        rosrust::publish::<Bool>(&format!("{}/charging", current_turtle()), 10).unwrap();
+    charging_publisher.send(Bool { data: true }).unwrap();
+
+    let loop_rate = rosrust::rate(1.0);
+    while get_battery_level().unwrap_or(0.0) < dock.resume_level && rosrust::is_ok() {
+        ros_debug!("charging: {:?}", get_battery_level());
+        // re-send periodically, battery node might have missed first message
+        charging_publisher.send(Bool { data: true }).unwrap();
+        loop_rate.sleep();
+    }
+
+    charging_publisher.send(Bool { data: false }).unwrap();
+    ros_info!("battery charged ({:?}). resuming", get_battery_level());
+
+    rosrust::is_ok()
+}
+
+/// drives turtle through given waypoints using go_to_target. if checkpoint path
+/// is given progress is stored after every waypoint and run is resumed from
+/// existing checkpoint. checkpoint is removed once all waypoints are covered.
+/// if dock is given, battery is checked before every waypoint.
+fn run_coverage(
+    velocity_publisher: &mut Publisher<Twist>,
+    waypoints: &[Waypoint],
+    options: &CoverageOptions,
+) {
+    let kl = 0.5; // constant for changing linear speed proportionally to target distance
+    let ka = 4.0; //constant for changing angular speed proportionally to target yaw
+
+    let checkpoint_path = options.checkpoint_path;
+    let mut start_step = 0;
+    let mut elapsed_before = 0.0;
+
+    if let Some(checkpoint) = checkpoint_path.and_then(Checkpoint::load) {
+        // resume step depends on where the turtle is, position is unknown until first pose
+        wait_for_positions(&[current_turtle()]);
+        let turtle_position = get_current_position();
+        start_step = resume_step(waypoints, &checkpoint, turtle_position.x, turtle_position.y);
+        elapsed_before = checkpoint.elapsed_secs;
+        ros_info!(
+            "resuming coverage from step {}/{} (checkpoint: {:?})",
+            start_step,
+            waypoints.len(),
+            checkpoint
+        );
+    }
+
+    let t0 = SystemTime::now();
+
+    for (step, waypoint) in waypoints.iter().enumerate().skip(start_step) {
+        wait_while_paused(velocity_publisher);
+        if !rosrust::is_ok() {
+            ros_info!("coverage interrupted at step {}/{}", step, waypoints.len());
+            return;
+        }
+
+        if let Some(dock) = &options.dock {
+            let battery_low = get_battery_level().is_some_and(|level| level < dock.low_level);
+            if battery_low && !recharge(velocity_publisher, dock) {
+                return;
+            }
+        }
+
+        go_to_target(velocity_publisher, waypoint.x, waypoint.y, kl, ka);
+
+        let elapsed_secs = elapsed_before + t0.elapsed().map(|e| e.as_secs_f64()).unwrap_or(0.0);
+        ros_info!(
+            "waypoint {}/{} reached. elapsed: {:.1}s",
+            step + 1,
+            waypoints.len(),
+            elapsed_secs
+        );
+
+        if let Some(path) = checkpoint_path {
+            let turtle_position = get_current_position();
+            let checkpoint = Checkpoint {
+                step: step + 1,
+                x: turtle_position.x,
+                y: turtle_position.y,
+                yaw: turtle_position.yaw,
+                elapsed_secs,
+            };
+            if let Err(e) = checkpoint.save(path) {
+                ros_err!("unable to save checkpoint {:?}: {:?}", path, e);
+            }
+        }
+    }
+
+    if let Some(path) = checkpoint_path {
+        if rosrust::is_ok() {
+            fs::remove_file(path).ok();
+        }
+    }
+}
+
+/// rectangular area to be cleaned
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct Area {
+    pub x_min: f64,
+    pub x_max: f64,
+    pub y_min: f64,
+    pub y_max: f64,
+}
+
+/// area swept by grid cleaning (turtlesim arena minus 1 m margin)
+const CLEANING_AREA: Area = Area {
+    x_min: 1.0,
+    x_max: 10.0,
+    y_min: 1.0,
+    y_max: 10.0,
+};
+
+/// how cleaning area is split between turtles. each turtle gets one cell.
+#[derive(Debug, Clone, PartialEq)]
+enum Partition {
+    /// given number of vertical stripes of equal width
+    Stripes(usize),
+    /// Voronoi cells around given seeds (initial turtle positions)
+    Voronoi(Vec<(f64, f64)>),
+}
+
+impl Partition {
+    /// index of cell containing point [x, y]
+    fn owner(&self, area: &Area, x: f64, y: f64) -> usize {
+        match self {
+            Partition::Stripes(count) => {
+                let relative_x = ((x - area.x_min) / (area.x_max - area.x_min)).max(0.0);
+                ((relative_x * *count as f64).floor() as usize).min(count - 1)
+            }
+            Partition::Voronoi(seeds) => seeds
+                .iter()
+                .map(|(seed_x, seed_y)| calculate_distance_2d(x, y, *seed_x, *seed_y))
+                .enumerate()
+                .min_by(|(_, d0), (_, d1)| d0.total_cmp(d1))
+                .map(|(i, _)| i)
+                .unwrap_or(0),
+        }
+    }
+}
+
+/// lawnmower path over the part of area belonging to given cell. area is swept in
+/// columns `spacing` apart. both stripes and Voronoi cells are convex so every
+/// column crosses the cell in (at most) one interval.
+fn partition_waypoints(
+    area: &Area,
+    partition: &Partition,
+    cell: usize,
+    spacing: f64,
+) -> Vec<Waypoint> {
+    let resolution = 0.05; // sampling step along column
+    let samples = ((area.y_max - area.y_min) / resolution).round() as usize;
+    let columns = ((area.x_max - area.x_min) / spacing).floor() as usize;
+
+    let mut waypoints = vec![];
+    let mut northwards = true;
+
+    for column in 0..=columns {
+        let x = area.x_min + column as f64 * spacing;
+        let owned: Vec<f64> = (0..=samples)
+            .map(|i| area.y_min + i as f64 * resolution)
+            .filter(|y| partition.owner(area, x, *y) == cell)
+            .collect();
+
+        if let (Some(&y_low), Some(&y_high)) = (owned.first(), owned.last()) {
+            if northwards {
+                waypoints.push(Waypoint { x, y: y_low });
+                waypoints.push(Waypoint { x, y: y_high });
+            } else {
+                waypoints.push(Waypoint { x, y: y_high });
+                waypoints.push(Waypoint { x, y: y_low });
+            }
+            northwards = !northwards;
+        }
+    }
+
+    waypoints
+}
+
+/// area split into small square cells remembering which of them were already visited
+#[derive(Debug, Clone)]
+struct CoverageGrid {
+    area: Area,
+    cell_size: f64,
+    columns: usize,
+    rows: usize,
+    visited: Vec<bool>,
+}
+
+impl CoverageGrid {
+    fn new(area: Area, cell_size: f64) -> CoverageGrid {
+        let columns = ((area.x_max - area.x_min) / cell_size).ceil() as usize;
+        let rows = ((area.y_max - area.y_min) / cell_size).ceil() as usize;
+        CoverageGrid {
+            area,
+            cell_size,
+            columns,
+            rows,
+            visited: vec![false; columns * rows],
+        }
+    }
+
+    /// marks all cells whose center is within radius from [x, y] as visited
+    fn mark(&mut self, x: f64, y: f64, radius: f64) {
+        for row in 0..self.rows {
+            for column in 0..self.columns {
+                let cell_x = self.area.x_min + (column as f64 + 0.5) * self.cell_size;
+                let cell_y = self.area.y_min + (row as f64 + 0.5) * self.cell_size;
+                if calculate_distance_2d(x, y, cell_x, cell_y) <= radius {
+                    self.visited[row * self.columns + column] = true;
+                }
+            }
+        }
+    }
+
+    /// visited fraction of the area (0.0 - 1.0)
+    fn coverage(&self) -> f64 {
+        let visited = self.visited.iter().filter(|v| **v).count();
+        visited as f64 / self.visited.len() as f64
+    }
+}
+
+/// blocks until pose of every given turtle is received
+fn wait_for_positions(namespaces: &[String]) {
+    let loop_rate = rosrust::rate(10.0);
+    while rosrust::is_ok() && namespaces.iter().any(|ns| find_position(ns).is_none()) {
+        loop_rate.sleep();
+    }
+}
+
+/// splits cleaning area between given turtles and cleans it in parallel (one thread per turtle).
+/// coverage of each turtle and combined coverage is sampled from turtle poses and reported at the end.
+fn cooperative_clean(namespaces: &[String], partition: Partition) {
+    let spacing = 1.0; // distance between cleaning lanes, also cleaning width of turtle
+    let turtle_count = namespaces.len();
+
+    // per turtle grids followed by combined one
+    let grids = Arc::new(RwLock::new(vec![
+        CoverageGrid::new(CLEANING_AREA, 0.25);
+        turtle_count + 1
+    ]));
+    let cleaning = Arc::new(AtomicBool::new(true));
+
+    let sampler = {
+        let grids = Arc::clone(&grids);
+        let cleaning = Arc::clone(&cleaning);
+        let namespaces = namespaces.to_vec();
+        thread::spawn(move || {
+            let loop_rate = rosrust::rate(10.0);
+            while cleaning.load(Ordering::SeqCst) && rosrust::is_ok() {
+                let mut grids = grids.write().unwrap();
+                for (i, namespace) in namespaces.iter().enumerate() {
+                    let position = get_position(namespace);
+                    grids[i].mark(position.x, position.y, spacing / 2.0);
+                    grids[turtle_count].mark(position.x, position.y, spacing / 2.0);
+                }
+                drop(grids);
+                loop_rate.sleep();
+            }
+        })
+    };
+
+    let t0 = SystemTime::now();
+
+    let workers: Vec<_> = namespaces
+        .iter()
+        .enumerate()
+        .map(|(cell, namespace)| {
+            let namespace = namespace.clone();
+            let waypoints = partition_waypoints(&CLEANING_AREA, &partition, cell, spacing);
+            thread::spawn(move || {
+                set_current_turtle(&namespace);
+                ros_info!(
+                    "{} cleaning cell {} ({} waypoints)",
+                    namespace,
+                    cell,
+                    waypoints.len()
+                );
+                let t0 = SystemTime::now();
+                run_coverage(
+                    &mut get_publisher(),
+                    &waypoints,
+                    &CoverageOptions::default(),
+                );
+                t0.elapsed().unwrap().as_secs_f64()
+            })
+        })
+        .collect();
+
+    let durations: Vec<f64> = workers.into_iter().map(|w| w.join().unwrap()).collect();
+    let completion_time = t0.elapsed().unwrap().as_secs_f64();
+
+    cleaning.store(false, Ordering::SeqCst);
+    sampler.join().unwrap();
+
+    let grids = grids.read().unwrap();
+    for (i, namespace) in namespaces.iter().enumerate() {
+        ros_info!(
+            "{} finished in {:.1}s. coverage: {:.1}%",
+            namespace,
+            durations[i],
+            grids[i].coverage() * 100.0
+        );
+    }
+    ros_info!(
+        "cooperative cleaning finished in {:.1}s. combined coverage: {:.1}%",
+        completion_time,
+        grids[turtle_count].coverage() * 100.0
+    );
+}
+
+/// arrangement of followers relative to leader
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Formation {
+    /// followers abreast of leader, alternating left and right
+    Line,
+    /// V shape behind leader, alternating left and right
+    Wedge,
+    /// followers in single file behind leader
+    Column,
+}
+
+impl Formation {
+    fn parse(name: &str) -> Option<Formation> {
+        match name {
+            "line" => Some(Formation::Line),
+            "wedge" => Some(Formation::Wedge),
+            "column" => Some(Formation::Column),
+            _ => None,
+        }
+    }
+
+    /// position of n-th follower (0 based) in leader frame as (behind, left) in meters
+    fn offset(&self, follower: usize, spacing: f64) -> (f64, f64) {
+        let rank = (follower / 2 + 1) as f64;
+        let side = if follower.is_multiple_of(2) {
+            1.0
+        } else {
+            -1.0
+        };
+
+        match self {
+            Formation::Line => (0.0, side * rank * spacing),
+            Formation::Wedge => (rank * spacing, side * rank * spacing),
+            Formation::Column => ((follower + 1) as f64 * spacing, 0.0),
+        }
+    }
+}
+
+/// converts offset (behind, left) in leader frame into target point in world frame
+fn formation_target(leader: &TurtlePosition, behind: f64, left: f64) -> (f64, f64) {
+    let (sin_yaw, cos_yaw) = leader.yaw.sin_cos();
+    (
+        leader.x - behind * cos_yaw - left * sin_yaw,
+        leader.y - behind * sin_yaw + left * cos_yaw,
+    )
+}
+
+/// keeps turtle controlled by current thread at given offset from leader using follower_controller
+/// (like go_to_target, but target moves with leader). once in place, turtle aligns its yaw
+/// with leader. runs until node is shut down.
+fn follow_leader(leader: &str, behind: f64, left: f64, k_linear: f64, k_angular: f64) {
+    let velocity_publisher = get_publisher();
+    let mut velocity_msg = Twist::default();
+
+    let loop_rate = rosrust::rate(10.0);
+
+    while rosrust::is_ok() {
+        let leader_position = get_position(leader);
+        let turtle_position = get_current_position();
+        let (target_x, target_y) = formation_target(&leader_position, behind, left);
+
+        let target_distance =
+            calculate_distance_2d(turtle_position.x, turtle_position.y, target_x, target_y);
+
+        if target_distance < 0.05 {
+            velocity_msg.linear.x = 0.0;
+            velocity_msg.angular.z =
+                k_angular * normalize_angle(leader_position.yaw - turtle_position.yaw);
+        } else {
+            let (linear_speed, angular_speed) =
+                follower_controller(&turtle_position, target_x, target_y, k_linear, k_angular);
+            velocity_msg.linear.x = linear_speed;
+            velocity_msg.angular.z = angular_speed;
+        }
+
+        send_velocity(&velocity_publisher, velocity_msg.clone());
+        loop_rate.sleep();
+    }
+
+    velocity_publisher.send(Twist::default()).unwrap();
+}
+
+/// followers keep given formation behind (or next to) leader. one thread per follower.
+fn formation_drive(leader: &str, followers: &[String], formation: Formation, spacing: f64) {
+    let workers: Vec<_> = followers
+        .iter()
+        .enumerate()
+        .map(|(i, follower)| {
+            let (behind, left) = formation.offset(i, spacing);
+            let leader = leader.to_string();
+            let follower = follower.clone();
+            thread::spawn(move || {
+                set_current_turtle(&follower);
+                ros_info!(
+                    "{} following {} at offset behind: {} left: {}",
+                    follower,
+                    leader,
+                    behind,
+                    left
+                );
+                follow_leader(&leader, behind, left, 0.5, 4.0);
+            })
+        })
+        .collect();
+
+    for worker in workers {
+        worker.join().unwrap();
+    }
+}
+
 ///
 /// caller functions below. called form main. after parsing command line args
 /// they will call core movement functions.
@@ -409,27 +1261,198 @@ fn grid_clean() {
 }
 
 fn grid_clean2() {
-    let mut publisher = get_publisher();
+    run_coverage(
+        &mut get_publisher(),
+        &grid_waypoints(),
+        &CoverageOptions::default(),
+    );
+}
 
-    let kl = 0.5; // constant for changing linear speed proportionally to target distance
-    let ka = 4.0; //constant for changing angular speed proportionally to target yaw
+/// same path as grid_clean2 but progress is checkpointed into file
+/// (args[2], defaults to turtle_cleaner.checkpoint) and resumed on restart
+fn resumable_grid_clean(args: Vec<String>) {
+    let checkpoint_path = args
+        .get(2)
+        .cloned()
+        .unwrap_or_else(|| "turtle_cleaner.checkpoint".to_string());
 
-    go_to_target(&mut publisher, 1.0, 1.0, kl, ka);
+    ros_info!(
+        "calling resumable_grid_clean. checkpoint_path: {}",
+        checkpoint_path
+    );
 
-    for i in (2..5).step_by(1) {
-        go_to_target(&mut publisher, i as f64, 1.0, kl, ka);
-        go_to_target(&mut publisher, i as f64, 10.0, kl, ka);
-        go_to_target(&mut publisher, i as f64 + 1.0, 10.0, kl, ka);
-        go_to_target(&mut publisher, i as f64 + 1.0, 1.0, kl, ka);
-    }
+    run_coverage(
+        &mut get_publisher(),
+        &grid_waypoints(),
+        &CoverageOptions {
+            checkpoint_path: Some(Path::new(&checkpoint_path)),
+            ..Default::default()
+        },
+    );
+}
+
+/// grid cleaning which returns to dock [args[2], args[3]] when battery drops below
+/// args[4] (default 0.2) and resumes once charged to args[5] (default 0.95).
+/// if args[6] is true battery is simulated by cleaner itself (use with turtles
+/// which do not publish battery_state, e.g. original turtlesim).
+/// args[7] is namespace of the cleaning turtle (default /turtle1)
+fn battery_aware_grid_clean(args: Vec<String>) {
+    let dock = Dock {
+        x: args[2].parse::<f64>().unwrap(),
+        y: args[3].parse::<f64>().unwrap(),
+        low_level: args
+            .get(4)
+            .and_then(|a| a.parse::<f64>().ok())
+            .unwrap_or(0.2),
+        resume_level: args
+            .get(5)
+            .and_then(|a| a.parse::<f64>().ok())
+            .unwrap_or(0.95),
+    };
+    let simulate = args
+        .get(6)
+        .and_then(|a| a.parse::<bool>().ok())
+        .unwrap_or(false);
+
+    let turtle = args
+        .get(7)
+        .cloned()
+        .unwrap_or_else(|| DEFAULT_TURTLE.to_string());
+
+    ros_info!(
+        "calling battery_aware_grid_clean. turtle: {} dock: {:?} simulate battery: {}",
+        turtle,
+        dock,
+        simulate
+    );
+
+    // motion functions (and recharge) work with turtle of current thread
+    set_current_turtle(&turtle);
+    let _position_subscriber = if turtle != DEFAULT_TURTLE {
+        // default turtle is already subscribed in main
+        Some(subscribe_position(&turtle))
+    } else {
+        None
+    };
+    wait_for_positions(std::slice::from_ref(&turtle));
+    let _battery_subscriber = subscribe_battery(&turtle);
+
+    let _battery = if simulate {
+        // battery of the cleaning turtle, recharge() signals docking to its charging topic
+        Some(simulate_battery(&turtle, BatteryModel::default(), 10.0))
+    } else {
+        None
+    };
+
+    run_coverage(
+        &mut get_publisher(),
+        &grid_waypoints(),
+        &CoverageOptions {
+            dock: Some(dock),
+            ..Default::default()
+        },
+    );
+}
+
+/// cleaning by multiple turtles. args[2] is partitioning (stripes or voronoi),
+/// remaining args are turtle namespaces (default /turtle1).
+/// collision avoidance is enabled, all discovered turtles (not only cleaning ones) are avoided.
+fn cooperative_clean_caller(args: Vec<String>) {
+    let partition_kind = args.get(2).map(String::as_str).unwrap_or("stripes");
+    let namespaces: Vec<String> = if args.len() > 3 {
+        args[3..].to_vec()
+    } else {
+        vec![DEFAULT_TURTLE.to_string()]
+    };
+
+    ros_info!(
+        "calling cooperative_clean. partition: {} turtles: {:?}",
+        partition_kind,
+        namespaces
+    );
+
+    let mut observed = turtle_namespaces("pose", "turtlesim/Pose");
+    observed.extend(namespaces.iter().cloned());
+    observed.sort();
+    observed.dedup();
+
+    let _subscribers: Vec<_> = observed
+        .iter()
+        .filter(|namespace| namespace.as_str() != DEFAULT_TURTLE) // already subscribed in main
+        .map(|namespace| subscribe_position(namespace))
+        .collect();
+    wait_for_positions(&namespaces);
+
+    *COLLISION_AVOIDANCE.write().unwrap() = Some(AvoidanceParams::default());
+
+    let partition = match partition_kind {
+        "stripes" => Partition::Stripes(namespaces.len()),
+        "voronoi" => Partition::Voronoi(
+            namespaces
+                .iter()
+                .map(|namespace| {
+                    let position = get_position(namespace);
+                    (position.x, position.y)
+                })
+                .collect(),
+        ),
+        _ => {
+            ros_err!("unsupported partition {}", partition_kind);
+            return;
+        }
+    };
+
+    cooperative_clean(&namespaces, partition);
+}
+
+/// formation driving. args[2] is formation (line, wedge or column), args[3] spacing in meters,
+/// args[4] leader namespace, remaining args follower namespaces, e.g.
+/// 12 wedge 1.0 /turtle1 /turtle2 /turtle3
+fn formation_caller(args: Vec<String>) {
+    let formation = match Formation::parse(&args[2]) {
+        Some(formation) => formation,
+        None => {
+            ros_err!("unsupported formation {}", args[2]);
+            return;
+        }
+    };
+    let spacing = args[3].parse::<f64>().unwrap();
+    let leader = args[4].clone();
+    let followers = args[5..].to_vec();
+
+    ros_info!(
+        "calling formation_drive. formation: {:?} spacing: {} leader: {} followers: {:?}",
+        formation,
+        spacing,
+        leader,
+        followers
+    );
+
+    let _subscribers: Vec<_> = followers
+        .iter()
+        .chain(std::iter::once(&leader))
+        .filter(|namespace| namespace.as_str() != DEFAULT_TURTLE) // already subscribed in main
+        .map(|namespace| subscribe_position(namespace))
+        .collect();
+
+    let mut turtles = followers.clone();
+    turtles.push(leader.clone());
+    wait_for_positions(&turtles);
+
+    formation_drive(&leader, &followers, formation, spacing);
 }
 
 fn spiral_clean() {
     spiral_move(&mut get_publisher(), 0.0, 2.0);
 }
 
+/// creates velocity publisher for turtle controlled by current thread
 fn get_publisher() -> Publisher<Twist> {
-    rosrust::publish::<rosrust_msg::geometry_msgs::Twist>("/turtle1/cmd_vel", 100).unwrap()
+    rosrust::publish::<rosrust_msg::geometry_msgs::Twist>(
+        &format!("{}/cmd_vel", current_turtle()),
+        100,
+    )
+    .unwrap()
 }
 
 fn main() {
@@ -443,15 +1466,17 @@ fn main() {
 
     let mut velocity_publisher = get_publisher();
 
-    let _raii_subscriber =
-        rosrust::subscribe("/turtle1/pose", 100, move |pose: msg::turtlesim::Pose| {
-            set_current_position(&TurtlePosition {
-                x: pose.x as f64,
-                y: pose.y as f64,
-                yaw: pose.theta as f64,
-            });
-        })
-        .unwrap();
+    let _raii_subscriber = subscribe_position(DEFAULT_TURTLE);
+
+    let _pause_subscriber = rosrust::subscribe(
+        "/turtle_cleaner/pause",
+        10,
+        move |pause: rosrust_msg::std_msgs::Bool| {
+            ros_info!("pause request: {}", pause.data);
+            CLEANING_PAUSED.store(pause.data, Ordering::SeqCst);
+        },
+    )
+    .unwrap();
 
     let args = rosrust::args();
 
@@ -467,6 +1492,10 @@ fn main() {
         6 => grid_clean(),
         7 => grid_clean2(),
         8 => spiral_clean(),
+        9 => resumable_grid_clean(args),
+        10 => battery_aware_grid_clean(args),
+        11 => cooperative_clean_caller(args),
+        12 => formation_caller(args),
         _ => {
             ros_err!("unsupported action specified {}", switch_value);
         }
@@ -517,4 +1546,211 @@ mod tests {
         assert_eq!(angle_to_target_2d(3.0, 3.0, 3.0, 4.0).to_degrees(), 90_f64);
         assert_eq!(angle_to_target_2d(3.0, 3.0, 2.0, 4.0).to_degrees(), 135_f64);
     }
+
+    #[test]
+    fn test_checkpoint_roundtrip() {
+        let checkpoint = Checkpoint {
+            step: 7,
+            x: 3.5,
+            y: 10.0,
+            yaw: -1.25,
+            elapsed_secs: 42.5,
+        };
+        assert_eq!(Checkpoint::parse(&checkpoint.serialize()), Some(checkpoint));
+        assert_eq!(Checkpoint::parse("step=abc"), None);
+        assert_eq!(Checkpoint::parse("foo=1"), None);
+    }
+
+    #[test]
+    fn test_resume_step() {
+        let waypoints = grid_waypoints();
+        let checkpoint = Checkpoint {
+            step: 3,
+            ..Default::default()
+        };
+        // turtle near covered waypoint -> continue with the nearest uncovered one
+        assert_eq!(resume_step(&waypoints, &checkpoint, 2.0, 9.0), 3);
+        // turtle moved further along the path -> nearest uncovered waypoint there
+        assert_eq!(resume_step(&waypoints, &checkpoint, 4.2, 1.2), 8);
+        // turtle already at nearest uncovered waypoint -> skip it
+        assert_eq!(resume_step(&waypoints, &checkpoint, 3.0, 10.0), 4);
+        // checkpoint beyond path -> nothing left to do
+        let checkpoint = Checkpoint {
+            step: 100,
+            ..Default::default()
+        };
+        assert_eq!(
+            resume_step(&waypoints, &checkpoint, 0.0, 0.0),
+            waypoints.len()
+        );
+    }
+
+    #[test]
+    fn test_normalize_angle() {
+        assert!((normalize_angle(3.0 * PI / 2.0) + PI / 2.0).abs() < 1e-9);
+        assert!((normalize_angle(-3.0 * PI / 2.0) - PI / 2.0).abs() < 1e-9);
+        assert_eq!(normalize_angle(PI), PI);
+        assert_eq!(normalize_angle(0.5), 0.5);
+    }
+
+    #[test]
+    fn test_turtle_priority() {
+        assert_eq!(turtle_priority("/turtle1"), 1);
+        assert_eq!(turtle_priority("/turtle12"), 12);
+        assert_eq!(turtle_priority("/leader"), 0);
+    }
+
+    /// headless simulation: turtles swap their positions (crossing in the middle of arena)
+    /// controlled by go_to_target-like proportional controller. separation is checked after each step.
+    #[test]
+    fn test_avoid_collisions_simulation() {
+        let params = AvoidanceParams::default();
+        let dt = 0.1;
+        let (k_linear, k_angular, max_speed) = (0.5, 4.0, 2.0);
+
+        let starts = [(2.0, 5.0), (8.0, 5.0), (5.0, 2.0), (5.0, 8.0), (2.5, 2.5)];
+        let goals = [(8.0, 5.0), (2.0, 5.0), (5.0, 8.0), (5.0, 2.0), (7.5, 7.5)];
+
+        let mut agents: Vec<Agent> = starts
+            .iter()
+            .enumerate()
+            .map(|(i, (x, y))| Agent {
+                x: *x,
+                y: *y,
+                yaw: angle_to_target_2d(*x, *y, goals[i].0, goals[i].1),
+                priority: i as u32 + 1,
+            })
+            .collect();
+
+        let mut min_distance = f64::MAX;
+
+        for _ in 0..3000 {
+            let commands: Vec<(f64, f64)> = agents
+                .iter()
+                .enumerate()
+                .map(|(i, agent)| {
+                    let (goal_x, goal_y) = goals[i];
+                    let distance = calculate_distance_2d(agent.x, agent.y, goal_x, goal_y);
+                    let angle_error = normalize_angle(
+                        angle_to_target_2d(agent.x, agent.y, goal_x, goal_y) - agent.yaw,
+                    );
+                    let linear_speed = (k_linear * distance).min(max_speed);
+                    let angular_speed = k_angular * angle_error;
+
+                    let others: Vec<Agent> = agents
+                        .iter()
+                        .enumerate()
+                        .filter(|(j, _)| *j != i)
+                        .map(|(_, a)| *a)
+                        .collect();
+                    avoid_collisions(agent, &others, linear_speed, angular_speed, &params)
+                })
+                .collect();
+
+            for (agent, (linear_speed, angular_speed)) in agents.iter_mut().zip(commands) {
+                agent.x += linear_speed * agent.yaw.cos() * dt;
+                agent.y += linear_speed * agent.yaw.sin() * dt;
+                agent.yaw = normalize_angle(agent.yaw + angular_speed * dt);
+            }
+
+            for i in 0..agents.len() {
+                for j in i + 1..agents.len() {
+                    let (a, b) = (&agents[i], &agents[j]);
+                    min_distance = min_distance.min(calculate_distance_2d(a.x, a.y, b.x, b.y));
+                }
+            }
+        }
+
+        assert!(
+            min_distance >= params.min_separation - 1e-9,
+            "min distance {}",
+            min_distance
+        );
+
+        for (agent, (goal_x, goal_y)) in agents.iter().zip(goals) {
+            assert!(
+                calculate_distance_2d(agent.x, agent.y, goal_x, goal_y) < 0.1,
+                "{:?} did not reach goal [{}, {}]",
+                agent,
+                goal_x,
+                goal_y
+            );
+        }
+    }
+
+    #[test]
+    fn test_formation_offsets() {
+        assert_eq!(Formation::Line.offset(0, 1.0), (0.0, 1.0));
+        assert_eq!(Formation::Line.offset(1, 1.0), (0.0, -1.0));
+        assert_eq!(Formation::Line.offset(2, 1.0), (0.0, 2.0));
+        assert_eq!(Formation::Wedge.offset(0, 0.5), (0.5, 0.5));
+        assert_eq!(Formation::Wedge.offset(3, 0.5), (1.0, -1.0));
+        assert_eq!(Formation::Column.offset(2, 1.0), (3.0, 0.0));
+        assert_eq!(Formation::parse("wedge"), Some(Formation::Wedge));
+        assert_eq!(Formation::parse("circle"), None);
+    }
+
+    #[test]
+    fn test_formation_target() {
+        // leader heading north, follower 1 m behind and 0.5 m to the left
+        let leader = TurtlePosition {
+            x: 5.0,
+            y: 5.0,
+            yaw: 90_f64.to_radians(),
+        };
+        let (x, y) = formation_target(&leader, 1.0, 0.5);
+        assert!((x - 4.5).abs() < 1e-9);
+        assert!((y - 4.0).abs() < 1e-9);
+
+        // leader heading east
+        let leader = TurtlePosition {
+            x: 5.0,
+            y: 5.0,
+            yaw: 0.0,
+        };
+        let (x, y) = formation_target(&leader, 1.0, 0.5);
+        assert!((x - 4.0).abs() < 1e-9);
+        assert!((y - 5.5).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_stripes_partition() {
+        let partition = Partition::Stripes(3);
+        assert_eq!(partition.owner(&CLEANING_AREA, 1.0, 5.0), 0);
+        assert_eq!(partition.owner(&CLEANING_AREA, 5.0, 5.0), 1);
+        assert_eq!(partition.owner(&CLEANING_AREA, 10.0, 5.0), 2);
+
+        // every lane is swept by exactly one turtle
+        let lanes: Vec<Vec<Waypoint>> = (0..3)
+            .map(|cell| partition_waypoints(&CLEANING_AREA, &partition, cell, 1.0))
+            .collect();
+        assert_eq!(lanes.iter().map(Vec::len).sum::<usize>(), 2 * 10);
+        assert_eq!(lanes[0][0], Waypoint { x: 1.0, y: 1.0 });
+        assert_eq!(lanes[0][1], Waypoint { x: 1.0, y: 10.0 });
+        assert_eq!(lanes[0][2], Waypoint { x: 2.0, y: 10.0 });
+    }
+
+    #[test]
+    fn test_voronoi_partition_coverage() {
+        let partition = Partition::Voronoi(vec![(2.0, 2.0), (8.0, 8.0), (2.0, 9.0)]);
+        let mut grid = CoverageGrid::new(CLEANING_AREA, 0.25);
+
+        for cell in 0..3 {
+            let waypoints = partition_waypoints(&CLEANING_AREA, &partition, cell, 1.0);
+            for w in &waypoints {
+                assert_eq!(partition.owner(&CLEANING_AREA, w.x, w.y), cell);
+            }
+            // sample straight segments between waypoints as turtle would drive them
+            for segment in waypoints.windows(2) {
+                for i in 0..=100 {
+                    let t = i as f64 / 100.0;
+                    let x = segment[0].x + t * (segment[1].x - segment[0].x);
+                    let y = segment[0].y + t * (segment[1].y - segment[0].y);
+                    grid.mark(x, y, 0.5);
+                }
+            }
+        }
+
+        assert!(grid.coverage() > 0.95);
+    }
 }
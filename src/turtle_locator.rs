@@ -1,19 +1,456 @@
 use crossterm::cursor;
-use crossterm::event::{read, Event, KeyCode, KeyEvent, KeyModifiers};
+use crossterm::event::{poll, read, Event, KeyCode, KeyEvent, KeyModifiers};
 use crossterm::execute;
-use crossterm::style::Print;
-use crossterm::terminal::{disable_raw_mode, enable_raw_mode, Clear, ClearType};
+use crossterm::style::{Attribute, Color, Print, ResetColor, SetAttribute, SetForegroundColor};
+use crossterm::terminal::{self, disable_raw_mode, enable_raw_mode, Clear, ClearType};
 use crossterm::Result;
-use rosrust;
-use std::io::stdout;
+use ros_tutorial::discovery::turtle_namespaces;
+use ros_tutorial::geofence::{Geofence, GeofenceState};
+use ros_tutorial::hud::RateMeter;
+use ros_tutorial::map::{parse_waypoints, MapView};
+use ros_tutorial::odometry::OdometryStats;
+use ros_tutorial::pose_log::{LogFormat, PoseLogger, PoseRecord};
+use rosrust::{ros_err, ros_info, Publisher, Subscriber};
+use rosrust_msg::std_msgs;
+use std::collections::{BTreeMap, HashMap, VecDeque};
+use std::fs;
+use std::io::{stdout, Stdout};
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
 
 mod msg {
     rosrust::rosmsg_include!(turtlesim / Pose);
 }
 
+/// how often the display is redrawn
+const REFRESH_PERIOD: Duration = Duration::from_millis(100);
+
+/// first terminal row of the turtle table (row above is taken by help)
+const TABLE_ROW: u16 = 1;
+
+/// turtle shown before any other is discovered
+const DEFAULT_TURTLE: &str = "/turtle1";
+
+/// how often turtles (their pose topics) are discovered
+const DISCOVERY_PERIOD: Duration = Duration::from_secs(2);
+
+/// log file is rotated when exceeding this size
+const DEFAULT_LOG_SIZE_MB: f64 = 10.0;
+
+/// turtle is stale when no pose was received for this long
+const STALE_TIMEOUT: Duration = Duration::from_secs(1);
+
+/// lines of odometry statistics of the selected turtle
+const STATISTICS_LINES: u16 = 4;
+
+/// number of remembered trail points
+const TRAIL_LENGTH: usize = 500;
+
+/// trail point is remembered only after turtle moved at least this distance
+const TRAIL_SPACING: f64 = 0.05;
+
+/// latest pose and recent trail of a turtle
+#[derive(Default)]
+struct Track {
+    pose: Option<msg::turtlesim::Pose>,
+    received: Option<Instant>,
+    rate: RateMeter,
+    trail: VecDeque<(f64, f64)>,
+    geofence: GeofenceState,
+    odometry: OdometryStats,
+}
+
+impl Track {
+    fn update(&mut self, pose: msg::turtlesim::Pose, now: Instant) {
+        let (x, y) = (pose.x as f64, pose.y as f64);
+        let moved = match self.trail.back() {
+            Some((last_x, last_y)) => (x - last_x).hypot(y - last_y) >= TRAIL_SPACING,
+            None => true,
+        };
+
+        if moved {
+            if self.trail.len() == TRAIL_LENGTH {
+                self.trail.pop_front();
+            }
+            self.trail.push_back((x, y));
+        }
+        self.odometry.update(
+            x,
+            y,
+            pose.linear_velocity as f64,
+            pose.angular_velocity as f64,
+            now,
+        );
+        self.pose = Some(pose);
+        self.received = Some(now);
+        self.rate.tick(now);
+    }
+
+    fn is_stale(&self, now: Instant) -> bool {
+        match self.received {
+            Some(received) => now.duration_since(received) > STALE_TIMEOUT,
+            None => true,
+        }
+    }
+}
+
+/// tracks of all turtles, by namespace
+type Tracks = Arc<Mutex<BTreeMap<String, Track>>>;
+
+/// geofence checked for every received pose
+struct Safety {
+    geofence: Geofence,
+    alert_publisher: Publisher<std_msgs::String>,
+    /// last alert (message, active) shown on the status line. logging it would
+    /// garble the display in raw mode
+    last_alert: Mutex<Option<(String, bool)>>,
+}
+
+impl Safety {
+    fn check(&self, namespace: &str, track: &mut Track) {
+        let pose = match &track.pose {
+            Some(pose) => pose,
+            None => return,
+        };
+
+        for alert in track
+            .geofence
+            .update(&self.geofence, pose.x as f64, pose.y as f64)
+        {
+            let message = alert.message(namespace);
+            *self.last_alert.lock().unwrap() = Some((message.clone(), alert.active));
+            self.alert_publisher
+                .send(std_msgs::String { data: message })
+                .unwrap();
+        }
+    }
+}
+
+/// starts tracking turtle publishing poses to <namespace>/pose
+fn subscribe_turtle(namespace: &str, tracks: &Tracks, safety: &Option<Arc<Safety>>) -> Subscriber {
+    tracks
+        .lock()
+        .unwrap()
+        .insert(namespace.to_string(), Track::default());
+
+    let tracks = Arc::clone(tracks);
+    let safety = safety.clone();
+    let ns = namespace.to_string();
+    rosrust::subscribe(
+        &format!("{}/pose", namespace),
+        100,
+        move |v: msg::turtlesim::Pose| {
+            if let Some(track) = tracks.lock().unwrap().get_mut(&ns) {
+                track.update(v, Instant::now());
+                if let Some(safety) = &safety {
+                    safety.check(&ns, track);
+                }
+            }
+        },
+    )
+    .unwrap()
+}
+
+/// subscribes turtles which are not subscribed yet
+fn discover_turtles(
+    subscribers: &mut HashMap<String, Subscriber>,
+    subscribe: impl Fn(&str) -> Subscriber,
+) {
+    for namespace in turtle_namespaces("pose", "turtlesim/Pose") {
+        subscribers
+            .entry(namespace.clone())
+            .or_insert_with(|| subscribe(&namespace));
+    }
+}
+
+fn table_header() -> String {
+    format!(
+        "  {:<12} {:>7} {:>7} {:>7} {:>7} {:>7} {:>6} {:>7}",
+        "turtle", "x", "y", "theta°", "linear", "angular", "Hz", "age [s]"
+    )
+}
+
+fn table_line(namespace: &str, track: &mut Track, selected: bool, now: Instant) -> String {
+    let mark = if selected { '>' } else { ' ' };
+    let rate = track.rate.rate(now);
+
+    match (&track.pose, track.received) {
+        (Some(pose), Some(received)) => {
+            format!(
+                "{} {:<12} {:7.3} {:7.3} {:+7.1} {:+7.2} {:+7.2} {:6.1} {:7.1}{}",
+                mark,
+                namespace,
+                pose.x,
+                pose.y,
+                (pose.theta as f64).to_degrees(),
+                pose.linear_velocity,
+                pose.angular_velocity,
+                rate,
+                now.duration_since(received).as_secs_f64(),
+                if track.is_stale(now) { "  STALE" } else { "" }
+            ) + &violations_text(&track.geofence)
+        }
+        _ => format!("{} {:<12} waiting for pose...", mark, namespace),
+    }
+}
+
+/// list of violated geofence zones
+fn violations_text(geofence: &GeofenceState) -> String {
+    let violations: Vec<&str> = geofence.violations().collect();
+    if violations.is_empty() {
+        String::new()
+    } else {
+        format!("  GEOFENCE: {}", violations.join(", "))
+    }
+}
+
+/// what is shown on the locator display
+struct View {
+    /// index of the selected turtle in the table
+    selected: usize,
+    /// only selected turtle is shown on the map
+    focused: bool,
+}
+
+fn draw(
+    stdout: &mut Stdout,
+    tracks: &mut BTreeMap<String, Track>,
+    view: &View,
+    waypoints: &[(f64, f64)],
+    safety: Option<&Safety>,
+) -> Result<()> {
+    let now = Instant::now();
+    let (width, height) = terminal::size()?;
+
+    execute!(
+        stdout,
+        cursor::MoveTo(0, TABLE_ROW),
+        Print(table_header()),
+        Clear(ClearType::UntilNewLine)
+    )?;
+
+    let mut turtles = vec![];
+    let mut trail = vec![];
+    let mut statistics = vec![];
+    for (i, (namespace, track)) in tracks.iter_mut().enumerate() {
+        let selected = i == view.selected;
+        let line = table_line(namespace, track, selected, now);
+        if selected {
+            statistics.push(format!("odometry of {}:", namespace));
+            statistics.extend(track.odometry.summary());
+        }
+
+        execute!(stdout, cursor::MoveTo(0, TABLE_ROW + 1 + i as u16))?;
+        if track.is_stale(now) || track.geofence.violations().next().is_some() {
+            execute!(stdout, SetForegroundColor(Color::Red))?;
+        }
+        if selected {
+            execute!(stdout, SetAttribute(Attribute::Reverse))?;
+        }
+        execute!(
+            stdout,
+            Print(line),
+            SetAttribute(Attribute::Reset),
+            ResetColor,
+            Clear(ClearType::UntilNewLine)
+        )?;
+
+        if view.focused && !selected {
+            continue;
+        }
+        if let Some(pose) = &track.pose {
+            turtles.push((pose.x as f64, pose.y as f64, pose.theta as f64));
+        }
+        trail.extend(track.trail.iter().copied());
+    }
+
+    let status_row = TABLE_ROW + tracks.len() as u16 + 2;
+    execute!(stdout, cursor::MoveTo(0, status_row))?;
+    match safety.and_then(|safety| safety.last_alert.lock().unwrap().clone()) {
+        Some((message, active)) => {
+            if active {
+                execute!(stdout, SetForegroundColor(Color::Red))?;
+            }
+            execute!(stdout, Print(format!("geofence: {}", message)), ResetColor)?;
+        }
+        None if safety.is_some() => execute!(stdout, Print("geofence: no alert"))?,
+        None => (),
+    }
+    execute!(stdout, Clear(ClearType::UntilNewLine))?;
+
+    let statistics_row = status_row + 2;
+    for (i, line) in statistics.iter().enumerate() {
+        execute!(
+            stdout,
+            cursor::MoveTo(0, statistics_row + i as u16),
+            Print(line),
+            Clear(ClearType::UntilNewLine)
+        )?;
+    }
+
+    let map_row = statistics_row + STATISTICS_LINES + 2;
+    let map = MapView::fit(width, height.saturating_sub(map_row));
+    let title = if view.focused {
+        "map: selected turtle"
+    } else {
+        "map: all turtles"
+    };
+
+    execute!(
+        stdout,
+        cursor::MoveTo(0, map_row - 1),
+        Print(title),
+        Clear(ClearType::UntilNewLine)
+    )?;
+    for (i, line) in map.render(&turtles, &trail, waypoints).iter().enumerate() {
+        execute!(stdout, cursor::MoveTo(0, map_row + i as u16), Print(line))?;
+    }
+    execute!(
+        stdout,
+        Clear(ClearType::FromCursorDown),
+        cursor::MoveTo(0, 0)
+    )?;
+
+    Ok(())
+}
+
+/// headless mode, writes poses of all turtles to the log until ctrl+c
+fn log_poses(path: &Path, format: LogFormat, max_size: u64) {
+    let logger = match PoseLogger::open(path, format, max_size) {
+        Ok(logger) => Arc::new(Mutex::new(logger)),
+        Err(e) => {
+            println!("unable to open log {:?}: {}", path, e);
+            return;
+        }
+    };
+
+    rosrust::init("turtle_locator");
+
+    let subscribe = |namespace: &str| {
+        let logger = Arc::clone(&logger);
+        let turtle = namespace.to_string();
+        rosrust::subscribe(
+            &format!("{}/pose", namespace),
+            100,
+            move |v: msg::turtlesim::Pose| {
+                let record = PoseRecord {
+                    stamp: SystemTime::now()
+                        .duration_since(UNIX_EPOCH)
+                        .unwrap()
+                        .as_secs_f64(),
+                    turtle: turtle.clone(),
+                    x: v.x,
+                    y: v.y,
+                    theta: v.theta,
+                    linear_velocity: v.linear_velocity,
+                    angular_velocity: v.angular_velocity,
+                };
+                if let Err(e) = logger.lock().unwrap().log(&record) {
+                    ros_err!("unable to write pose log: {}", e);
+                }
+            },
+        )
+        .unwrap()
+    };
+
+    let mut subscribers = HashMap::new();
+    subscribers.insert(DEFAULT_TURTLE.to_string(), subscribe(DEFAULT_TURTLE));
+    discover_turtles(&mut subscribers, subscribe);
+    ros_info!("logging poses to {:?} ({:?})", path, format);
+
+    let loop_rate = rosrust::rate(1.0 / DISCOVERY_PERIOD.as_secs_f64());
+    while rosrust::is_ok() {
+        discover_turtles(&mut subscribers, subscribe);
+        if let Err(e) = logger.lock().unwrap().flush() {
+            ros_err!("unable to write pose log: {}", e);
+        }
+        loop_rate.sleep();
+    }
+
+    // ctrl+c, no more poses will be received
+    drop(subscribers);
+    let mut logger = logger.lock().unwrap();
+    match logger.flush() {
+        Ok(()) => ros_info!("pose log closed, last file: {:?}", logger.current_path()),
+        Err(e) => ros_err!("unable to write pose log: {}", e),
+    }
+}
+
 fn main() -> Result<()> {
+    // usage:
+    //   turtle_locator [--geofence FILE] [waypoints file]       map and dashboard
+    //   turtle_locator --log csv|jsonl FILE [MAX_FILE_SIZE_MB]  headless pose logging
+    // waypoints file has one "x y" per line, geofence file see res/config/geofence.conf
+    let mut args = rosrust::args();
+    if args.get(1).map(String::as_str) == Some("--log") {
+        let usage = || println!("usage: turtle_locator --log csv|jsonl FILE [MAX_FILE_SIZE_MB]");
+        if args.len() < 4 || args.len() > 5 {
+            usage();
+            return Ok(());
+        }
+
+        let format = match LogFormat::parse(&args[2]) {
+            Ok(format) => format,
+            Err(e) => {
+                println!("{}", e);
+                return Ok(());
+            }
+        };
+        let max_size_mb = match args.get(4).map(|a| a.parse::<f64>()) {
+            None => DEFAULT_LOG_SIZE_MB,
+            Some(Ok(size)) if size.is_finite() && size > 0.0 => size,
+            _ => {
+                println!("MAX_FILE_SIZE_MB must be positive number");
+                usage();
+                return Ok(());
+            }
+        };
+
+        log_poses(
+            Path::new(&args[3]),
+            format,
+            (max_size_mb * 1024.0 * 1024.0) as u64,
+        );
+        return Ok(());
+    }
+
+    let geofence = match args.iter().position(|a| a == "--geofence") {
+        Some(i) if i + 1 < args.len() => {
+            let path = args.remove(i + 1);
+            args.remove(i);
+            match Geofence::load(Path::new(&path)) {
+                Ok(geofence) => Some(geofence),
+                Err(e) => {
+                    println!("unable to load geofence: {}", e);
+                    return Ok(());
+                }
+            }
+        }
+        Some(_) => {
+            println!("usage: turtle_locator --geofence FILE [waypoints file]");
+            return Ok(());
+        }
+        None => None,
+    };
+
+    let waypoints = match args.get(1) {
+        Some(path) => match fs::read_to_string(path)
+            .map_err(|e| e.to_string())
+            .and_then(|content| parse_waypoints(&content))
+        {
+            Ok(waypoints) => waypoints,
+            Err(e) => {
+                println!("unable to load waypoints {}: {}", path, e);
+                return Ok(());
+            }
+        },
+        None => vec![],
+    };
+
     enable_raw_mode()?;
-    let info_message = ">>>Press CTRL + q or CTRL + c to exit<<<";
+    let info_message =
+        ">>>Press CTRL + q or CTRL + c to exit, Up/Down to select turtle, Enter to focus on selected, Esc to show all<<<";
 
     execute!(
         stdout(),
@@ -24,22 +461,51 @@ fn main() -> Result<()> {
 
     rosrust::init("turtle_locator");
 
-    let _subscriber = rosrust::subscribe("/turtle1/pose", 100, move |v: msg::turtlesim::Pose| {
-        // rosrust::ros_info!("Received position: {:#?}", v);
+    let safety = geofence.map(|geofence| {
+        Arc::new(Safety {
+            geofence,
+            alert_publisher: rosrust::publish("/geofence/alert", 100).unwrap(),
+            last_alert: Mutex::new(None),
+        })
+    });
 
-        execute!(
-            stdout(),
-            Clear(ClearType::All),
-            Print(info_message),
-            cursor::MoveTo(0, 0),
-            Print(format!("\nReceived position: {:?}", v))
-        )
-        .unwrap();
-    })
-    .unwrap();
+    let tracks: Tracks = Arc::new(Mutex::new(BTreeMap::new()));
+    let mut subscribers = HashMap::new();
+    subscribers.insert(
+        DEFAULT_TURTLE.to_string(),
+        subscribe_turtle(DEFAULT_TURTLE, &tracks, &safety),
+    );
+    discover_turtles(&mut subscribers, |ns| {
+        subscribe_turtle(ns, &tracks, &safety)
+    });
+    let mut last_discovery = Instant::now();
+
+    let mut view = View {
+        selected: 0,
+        focused: false,
+    };
 
     loop {
-        execute!(stdout(), cursor::MoveTo(0, 0))?;
+        if last_discovery.elapsed() >= DISCOVERY_PERIOD {
+            discover_turtles(&mut subscribers, |ns| {
+                subscribe_turtle(ns, &tracks, &safety)
+            });
+            last_discovery = Instant::now();
+        }
+
+        draw(
+            &mut stdout(),
+            &mut tracks.lock().unwrap(),
+            &view,
+            &waypoints,
+            safety.as_deref(),
+        )?;
+
+        if !poll(REFRESH_PERIOD)? {
+            continue;
+        }
+
+        let turtle_count = tracks.lock().unwrap().len();
 
         match read().unwrap() {
             Event::Key(KeyEvent {
@@ -53,16 +519,52 @@ fn main() -> Result<()> {
                 execute!(
                     stdout(),
                     Clear(ClearType::All),
+                    cursor::MoveTo(0, 0),
                     Print("Quiting now. Bye!\n\n")
                 )?;
                 break;
             }
 
+            Event::Key(KeyEvent {
+                code: KeyCode::Up, ..
+            }) => view.selected = view.selected.saturating_sub(1),
+
+            Event::Key(KeyEvent {
+                code: KeyCode::Down,
+                ..
+            }) => view.selected = (view.selected + 1).min(turtle_count.saturating_sub(1)),
+
+            Event::Key(KeyEvent {
+                code: KeyCode::Enter,
+                ..
+            }) => view.focused = true,
+
+            Event::Key(KeyEvent {
+                code: KeyCode::Esc, ..
+            }) => view.focused = false,
+
+            Event::Resize(_, _) => {
+                execute!(
+                    stdout(),
+                    Clear(ClearType::All),
+                    cursor::MoveTo(0, 0),
+                    Print(info_message)
+                )?;
+            }
+
             _ => (),
         }
     }
 
     disable_raw_mode()?;
 
+    // odometry summary of all turtles
+    for (namespace, track) in tracks.lock().unwrap().iter() {
+        println!("odometry of {}:", namespace);
+        for line in track.odometry.summary() {
+            println!("  {}", line);
+        }
+    }
+
     Ok(())
 }
@@ -1,28 +1,233 @@
 use crossterm::cursor;
-use crossterm::event::{read, Event, KeyCode, KeyEvent, KeyModifiers};
+use crossterm::event::{poll, read, Event, KeyCode, KeyEvent, KeyModifiers};
 use crossterm::execute;
 use crossterm::style::Print;
 use crossterm::terminal::{disable_raw_mode, enable_raw_mode, Clear, ClearType};
-use std::io::stdout;
+use ros_tutorial::discovery::turtle_namespaces;
+use ros_tutorial::hud::{heading_arrow, RateMeter};
+use ros_tutorial::recording::{Playback, Recorder, Recording};
+use ros_tutorial::teleop::{Action, Keymap, Teleop, TeleopConfig};
+use rosrust::{Publisher, Subscriber};
+use rosrust_msg::geometry_msgs::Twist;
+use std::io::{stdout, Stdout};
+use std::path::Path;
+use std::sync::{mpsc, Arc, Mutex};
+use std::thread;
+use std::time::{Duration, Instant};
+
+mod msg {
+    rosrust::rosmsg_include!(turtlesim / Pose);
+}
+
+/// Twist messages are published with this rate while the turtle is moving
+const PUBLISH_RATE: f64 = 10.0;
+
+/// first terminal row of the status display (rows above are taken by help)
+const HUD_ROW: u16 = 4;
+
+/// turtle controlled when operator starts
+const DEFAULT_TURTLE: &str = "/turtle1";
+
+/// how often turtles (their cmd_vel topics) are discovered
+const DISCOVERY_PERIOD: Duration = Duration::from_secs(2);
+
+/// latest pose of the controlled turtle
+#[derive(Default)]
+struct PoseMonitor {
+    /// turtle being monitored. poses of previously controlled turtle, which may
+    /// still arrive after switching, are ignored
+    namespace: String,
+    pose: Option<msg::turtlesim::Pose>,
+    received: Option<Instant>,
+    rate: RateMeter,
+}
+
+/// turtle controlled by the operator
+struct Target {
+    namespace: String,
+    publisher: Publisher<Twist>,
+    _pose_subscriber: Subscriber,
+}
+
+impl Target {
+    /// starts publishing commands to <namespace>/cmd_vel and monitoring <namespace>/pose
+    fn connect(namespace: &str, pose_monitor: &Arc<Mutex<PoseMonitor>>) -> Target {
+        *pose_monitor.lock().unwrap() = PoseMonitor {
+            namespace: namespace.to_string(),
+            ..Default::default()
+        };
+
+        let monitor = Arc::clone(pose_monitor);
+        let monitored = namespace.to_string();
+        let pose_subscriber = rosrust::subscribe(
+            &format!("{}/pose", namespace),
+            100,
+            move |pose: msg::turtlesim::Pose| {
+                let now = Instant::now();
+                let mut monitor = monitor.lock().unwrap();
+                if monitor.namespace != monitored {
+                    return;
+                }
+                monitor.pose = Some(pose);
+                monitor.received = Some(now);
+                monitor.rate.tick(now);
+            },
+        )
+        .unwrap();
+
+        Target {
+            namespace: namespace.to_string(),
+            publisher: rosrust::publish::<Twist>(&format!("{}/cmd_vel", namespace), 100).unwrap(),
+            _pose_subscriber: pose_subscriber,
+        }
+    }
+
+    fn send(&self, linear_speed: f64, angular_speed: f64) {
+        let mut msg = Twist::default();
+        msg.linear.x = linear_speed;
+        msg.angular.z = angular_speed;
+        self.publisher.send(msg).unwrap();
+    }
+}
+
+/// discovers turtles (namespaces having cmd_vel topic) every DISCOVERY_PERIOD in background
+/// thread, so that slow ROS master does not delay publishing of velocity commands
+fn spawn_discovery() -> mpsc::Receiver<Vec<String>> {
+    let (sender, receiver) = mpsc::channel();
+    thread::spawn(move || {
+        while rosrust::is_ok() {
+            if sender
+                .send(turtle_namespaces("cmd_vel", "geometry_msgs/Twist"))
+                .is_err()
+            {
+                break;
+            }
+            thread::sleep(DISCOVERY_PERIOD);
+        }
+    });
+    receiver
+}
+
+/// discovered turtles, controlled one is always included
+fn with_target(mut turtles: Vec<String>, target: &Target) -> Vec<String> {
+    if !turtles.contains(&target.namespace) {
+        turtles.push(target.namespace.clone());
+        turtles.sort();
+    }
+    turtles
+}
+
+/// status line listing discovered turtles, controlled one is marked with *
+fn turtles_line(turtles: &[String], target: &Target) -> String {
+    let list: Vec<String> = turtles
+        .iter()
+        .enumerate()
+        .map(|(i, turtle)| {
+            let mark = if *turtle == target.namespace { "*" } else { "" };
+            format!("[{}] {}{}", i + 1, turtle, mark)
+        })
+        .collect();
+    format!("turtles: {}", list.join("  "))
+}
+
+/// overwrites status lines in place (without clearing the whole terminal, which flickers)
+fn draw_hud(stdout: &mut Stdout, lines: &[String]) {
+    for (i, line) in lines.iter().enumerate() {
+        execute!(
+            stdout,
+            cursor::MoveTo(0, HUD_ROW + i as u16),
+            Print(line),
+            Clear(ClearType::UntilNewLine)
+        )
+        .unwrap();
+    }
+    execute!(stdout, cursor::MoveTo(0, 0)).unwrap();
+}
+
+/// status lines describing pose of the turtle
+fn pose_lines(monitor: &mut PoseMonitor, namespace: &str, now: Instant) -> Vec<String> {
+    let rate = monitor.rate.rate(now);
+
+    match (&monitor.pose, monitor.received) {
+        (Some(pose), Some(received)) => vec![
+            format!("x: {:7.3}  y: {:7.3}", pose.x, pose.y),
+            format!(
+                "heading: {:+7.1}° {}",
+                (pose.theta as f64).to_degrees(),
+                heading_arrow(pose.theta as f64)
+            ),
+            format!(
+                "velocity: linear {:+.2} angular {:+.2}",
+                pose.linear_velocity, pose.angular_velocity
+            ),
+            format!(
+                "pose rate: {:5.1} Hz  last update: {:.1}s ago",
+                rate,
+                now.duration_since(received).as_secs_f64()
+            ),
+        ],
+        _ => vec![
+            format!("waiting for {}/pose ...", namespace),
+            String::new(),
+            String::new(),
+            String::new(),
+        ],
+    }
+}
+
+/// file for recorded macros when not specified on command line
+const DEFAULT_MACRO_FILE: &str = "teleop_macro.csv";
 
 fn main() {
+    // usage: turtle-operator [keymap file|-] [macro file]
+    // keymap file see res/config/operator_keymap.conf, - for default key bindings
+    let args = rosrust::args();
+    let keymap = match args.get(1).filter(|path| path.as_str() != "-") {
+        Some(path) => match Keymap::load(Path::new(path)) {
+            Ok(keymap) => keymap,
+            Err(e) => {
+                println!("unable to load keymap: {}", e);
+                return;
+            }
+        },
+        None => Keymap::default(),
+    };
+    let macro_path = Path::new(
+        args.get(2)
+            .map(String::as_str)
+            .unwrap_or(DEFAULT_MACRO_FILE),
+    )
+    .to_path_buf();
+
     let mut stdout = stdout();
     enable_raw_mode().unwrap();
-    let info_message =
-        ">>>Press CTRL + q or CTRL + c to exit, use arrows to navigate the turtle bot.<<<";
+    // help lists keys of the active keymap
+    let [motion_help, speed_help, selection_help] = keymap.help();
+    let info_message = format!(
+        ">>>Press CTRL + q or CTRL + c to exit, hold motion keys to navigate the turtle bot (combine them to drive in arcs). {}<<<",
+        motion_help
+    );
+    let speed_info_message = format!(">>>{}<<<", speed_help);
+    let selection_info_message = format!(">>>{}<<<", selection_help);
 
     execute!(
         stdout,
         Clear(ClearType::All),
         cursor::MoveTo(0, 0),
-        Print(info_message)
+        Print(info_message),
+        cursor::MoveTo(0, 1),
+        Print(speed_info_message),
+        cursor::MoveTo(0, 2),
+        Print(selection_info_message)
     )
     .unwrap();
 
     rosrust::init("turtle_operator");
 
-    let ros_publisher =
-        rosrust::publish::<rosrust_msg::geometry_msgs::Twist>("/turtle1/cmd_vel", 100).unwrap();
+    let pose_monitor = Arc::new(Mutex::new(PoseMonitor::default()));
+    let mut target = Target::connect(DEFAULT_TURTLE, &pose_monitor);
+    let discovery = spawn_discovery();
+    let mut turtles = with_target(vec![], &target);
 
     /*
     See http://wiki.ros.org/turtlesim/Tutorials/Rotating%20Left%20and%20Right
@@ -38,99 +243,192 @@ fn main() {
 
     */
 
-    loop {
-        execute!(stdout, cursor::MoveTo(0, 0)).unwrap();
-
-        match read().unwrap() {
-            Event::Key(KeyEvent {
-                code: KeyCode::Up,
-                modifiers: KeyModifiers::NONE,
-            }) => {
-                execute!(
-                    stdout,
-                    Clear(ClearType::All),
-                    Print(info_message),
-                    cursor::MoveTo(0, 0),
-                    Print("\nGoing Up")
-                )
-                .unwrap();
-                let mut msg = rosrust_msg::geometry_msgs::Twist::default();
-                msg.linear.x = 1.0;
-                ros_publisher.send(msg).unwrap();
+    let mut teleop = Teleop::new(TeleopConfig::default());
+    let period = Duration::from_secs_f64(1.0 / PUBLISH_RATE);
+    let mut last_update = Instant::now();
+    let mut moving = false;
+    let mut publish_rate = RateMeter::default();
+    let mut recorder: Option<Recorder> = None;
+    let mut playback: Option<Playback> = None;
+    let mut macro_status = String::from("idle");
+
+    'teleop: loop {
+        // collect key presses until it is time to publish next command
+        let deadline = last_update + period;
+        while let Some(timeout) = deadline.checked_duration_since(Instant::now()) {
+            if !poll(timeout).unwrap() {
+                break;
             }
 
-            Event::Key(KeyEvent {
-                code: KeyCode::Down,
-                modifiers: KeyModifiers::NONE,
-            }) => {
-                execute!(
-                    stdout,
-                    Clear(ClearType::All),
-                    Print(info_message),
-                    cursor::MoveTo(0, 0),
-                    Print("\nGoing down")
-                )
-                .unwrap();
-                let mut msg = rosrust_msg::geometry_msgs::Twist::default();
-                msg.linear.x = -1.0;
-                ros_publisher.send(msg).unwrap();
+            let action = match read().unwrap() {
+                Event::Key(KeyEvent {
+                    code: KeyCode::Char('q'),
+                    modifiers: KeyModifiers::CONTROL,
+                })
+                | Event::Key(KeyEvent {
+                    code: KeyCode::Char('c'),
+                    modifiers: KeyModifiers::CONTROL,
+                }) => {
+                    break 'teleop;
+                }
+
+                // shift is allowed (upper case letters)
+                Event::Key(KeyEvent { code, modifiers })
+                    if (modifiers - KeyModifiers::SHIFT).is_empty() =>
+                {
+                    match keymap.action(code) {
+                        Some(action) => action,
+                        None => continue,
+                    }
+                }
+
+                _ => continue,
+            };
+
+            teleop.apply(action, Instant::now());
+
+            match action {
+                // driving by hand cancels replay
+                Action::Move(_) | Action::Stop if playback.is_some() => {
+                    playback = None;
+                    macro_status = String::from("replay cancelled");
+                }
+
+                Action::ToggleRecording => match recorder.take() {
+                    Some(finished) => {
+                        let recording = finished.finish();
+                        // nothing was published while recording, the previous macro is kept
+                        macro_status = if recording.commands.is_empty() {
+                            String::from("nothing recorded, macro not saved")
+                        } else {
+                            match recording.save(&macro_path) {
+                                Ok(()) => format!(
+                                    "{} commands saved to {:?}",
+                                    recording.commands.len(),
+                                    macro_path
+                                ),
+                                Err(e) => format!("unable to save macro: {}", e),
+                            }
+                        };
+                    }
+                    None => {
+                        recorder = Some(Recorder::default());
+                        macro_status = String::from("recording");
+                    }
+                },
+
+                Action::Replay | Action::ReplayLoop => match Recording::load(&macro_path) {
+                    Ok(recording) if recording.commands.is_empty() => {
+                        macro_status = format!("macro {:?} is empty", macro_path)
+                    }
+                    Ok(recording) => {
+                        let looping = action == Action::ReplayLoop;
+                        playback = Some(Playback::new(recording, 1.0, looping, Instant::now()));
+                        macro_status = format!("replaying {:?} (loop: {})", macro_path, looping);
+                    }
+                    Err(e) => macro_status = format!("unable to load macro: {}", e),
+                },
+
+                _ => (),
             }
 
-            Event::Key(KeyEvent {
-                code: KeyCode::Left,
-                modifiers: KeyModifiers::NONE,
-            }) => {
-                execute!(
-                    stdout,
-                    Clear(ClearType::All),
-                    Print(info_message),
-                    cursor::MoveTo(0, 0),
-                    Print("\nRotating counter clockwise")
-                )
-                .unwrap();
-                let mut msg = rosrust_msg::geometry_msgs::Twist::default();
-                msg.angular.z = 0.5;
-                ros_publisher.send(msg).unwrap();
+            if action == Action::Stop {
+                // emergency stop, do not wait for next publish period
+                target.send(0.0, 0.0);
+                if let Some(recorder) = recorder.as_mut() {
+                    recorder.record(Instant::now(), 0.0, 0.0);
+                }
             }
 
-            Event::Key(KeyEvent {
-                code: KeyCode::Right,
-                modifiers: KeyModifiers::NONE,
-            }) => {
-                execute!(
-                    stdout,
-                    Clear(ClearType::All),
-                    Print(info_message),
-                    cursor::MoveTo(0, 0),
-                    Print("\nRotating clockwise")
-                )
-                .unwrap();
-
-                let mut msg = rosrust_msg::geometry_msgs::Twist::default();
-                msg.angular.z = -0.5;
-                ros_publisher.send(msg).unwrap();
+            let selected = match action {
+                Action::NextTurtle => turtles
+                    .iter()
+                    .position(|turtle| *turtle == target.namespace)
+                    .map(|i| (i + 1) % turtles.len()),
+                Action::SelectTurtle(n) => Some(n - 1),
+                _ => None,
+            };
+
+            if let Some(turtle) = selected.and_then(|i| turtles.get(i)) {
+                if *turtle != target.namespace {
+                    // stop previously controlled turtle before switching
+                    target.send(0.0, 0.0);
+                    teleop.stop();
+                    moving = false;
+                    if playback.take().is_some() {
+                        macro_status = String::from("replay cancelled");
+                    }
+                    target = Target::connect(turtle, &pose_monitor);
+                }
             }
+        }
 
-            Event::Key(KeyEvent {
-                code: KeyCode::Char('q'),
-                modifiers: KeyModifiers::CONTROL,
-            })
-            | Event::Key(KeyEvent {
-                code: KeyCode::Char('c'),
-                modifiers: KeyModifiers::CONTROL,
-            }) => {
-                execute!(
-                    stdout,
-                    Clear(ClearType::All),
-                    Print("Quiting now. Bye!\n\n")
-                )
-                .unwrap();
-                break;
+        let now = Instant::now();
+        let dt = now.duration_since(last_update).as_secs_f64();
+        last_update = now;
+
+        let (linear_speed, angular_speed) = teleop.update(now, dt);
+
+        // replayed macro overrides keyboard
+        let (linear_speed, angular_speed) = match playback.as_ref().map(|p| p.command(now)) {
+            Some(Some(command)) => command,
+            Some(None) => {
+                playback = None;
+                macro_status = String::from("replay finished");
+                (0.0, 0.0)
             }
+            None => (linear_speed, angular_speed),
+        };
+
+        if let Some(discovered) = discovery.try_iter().last() {
+            turtles = with_target(discovered, &target);
+        }
 
-            _ => (),
+        // keep publishing while moving + one final zero command to stop. when turtle stands
+        // still nothing is published, so that other nodes can control the turtle.
+        if linear_speed != 0.0 || angular_speed != 0.0 || moving {
+            target.send(linear_speed, angular_speed);
+            publish_rate.tick(now);
+            if let Some(recorder) = recorder.as_mut() {
+                recorder.record(now, linear_speed, angular_speed);
+            }
         }
+        moving = linear_speed != 0.0 || angular_speed != 0.0;
+
+        let (max_linear_speed, max_angular_speed) = teleop.max_speeds();
+        let mut lines = vec![
+            turtles_line(&turtles, &target),
+            format!(
+                "speed scale: linear x{:.2} ({:.2} m/s) angular x{:.2} ({:.2} rad/s)",
+                teleop.linear_scale, max_linear_speed, teleop.angular_scale, max_angular_speed
+            ),
+            format!(
+                "command: linear {:+.2} angular {:+.2} keys: {:?}  cmd_vel rate: {:4.1} Hz",
+                linear_speed,
+                angular_speed,
+                teleop.held_motions(now),
+                publish_rate.rate(now)
+            ),
+            format!("macro: {}", macro_status),
+            String::new(),
+        ];
+        lines.extend(pose_lines(
+            &mut pose_monitor.lock().unwrap(),
+            &target.namespace,
+            now,
+        ));
+        draw_hud(&mut stdout, &lines);
     }
 
+    // do not leave the turtle running
+    target.send(0.0, 0.0);
+
+    execute!(
+        stdout,
+        Clear(ClearType::All),
+        Print("Quiting now. Bye!\n\n")
+    )
+    .unwrap();
+
     disable_raw_mode().unwrap();
 }